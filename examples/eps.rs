@@ -7,7 +7,7 @@ extern crate eps_writer;
 
 use na::{Pnt2, Vec2, FloatPnt, FloatVec};
 use num::Zero;
-use space_colonization::{SpaceColonization, SqDist};
+use space_colonization::{SpaceColonization, SpaceColonizationBuilder, Coords};
 use eps_writer::{EpsDocument, Position, Points, Lines, SetRGB};
 use std::fs::File;
 use common::{MyPoint, Config};
@@ -18,20 +18,22 @@ pub mod common;
 const SCALE: f32 = 400.0;
 
 fn run<T, F>(config: &Config)
-    where T: MyPoint + FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug
+    where T: MyPoint + FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug
 {
     let mut rng = rand::thread_rng();
 
     let mut sc: SpaceColonization<T, F, ()> =
-        SpaceColonization::new(SqDist::from_dist(config.influence_radius),
-                               SqDist::from_dist(config.kill_distance),
-                               config.max_length,
-                               config.max_branches,
-                               config.move_distance);
+        SpaceColonizationBuilder::new(config.influence_radius,
+                                      config.kill_distance,
+                                      config.move_distance)
+            .max_length(config.max_length)
+            .max_branches(config.max_branches)
+            .build()
+            .unwrap();
 
     for _ in 0..config.n_roots {
-        sc.add_root_node(<T as MyPoint>::random(&mut rng));
+        sc.add_root_node(<T as MyPoint>::random(&mut rng)).unwrap();
     }
 
     for _ in 0..config.n_attraction_points {
@@ -77,9 +79,10 @@ fn run<T, F>(config: &Config)
             }
         }
 
-        let new_nodes = sc.next();
-
-        println!("Iteration: {}. New nodes: {:?}", i, new_nodes);
+        match sc.next() {
+            Some(report) => println!("Iteration: {}. {:?}", i, report),
+            None => break,
+        }
 
         i += 1;
     }
@@ -11,7 +11,8 @@ extern crate dot;
 use kiss3d::window::Window;
 use na::{Pnt2, Pnt3, Vec2, Vec3, FloatPnt, FloatVec};
 use num::Zero;
-use space_colonization::{SpaceColonization, SqDist, Attractor, ConnectAction};
+use space_colonization::{SpaceColonization, SpaceColonizationBuilder, SqDist, Attractor,
+                         ConnectAction, ConnectPolicy, Coords, InformationMerge};
 use common::{MyPoint, Config};
 use std::fmt::Debug;
 
@@ -30,24 +31,31 @@ impl Default for Information {
     }
 }
 
+// A node keeps the information it received last.
+impl InformationMerge for Information {}
+
 fn run<T, F>(config: &Config)
-    where T: MyPoint + FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug
+    where T: MyPoint + FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug
 {
     let mut rng = rand::thread_rng();
 
     let mut sc: SpaceColonization<T, F, Information> =
-        SpaceColonization::new(SqDist::from_dist(config.influence_radius),
-        SqDist::from_dist(config.kill_distance),
-        config.max_length,
-        config.max_branches,
-        config.move_distance);
+        SpaceColonizationBuilder::new(config.influence_radius,
+                                      config.kill_distance,
+                                      config.move_distance)
+            .max_length(config.max_length)
+            .max_branches(config.max_branches)
+            // connect target attractors with the closest node, not the oldest one.
+            .connect_policy(ConnectPolicy::Nearest)
+            .build()
+            .unwrap();
 
     // these are the source nodes. This is where we start growing.
     /*
     for src in 0..config.n_roots {
         //sc.add_root_node_with_information(<T as MyPoint>::random(&mut rng), Some(Information::Source(src)));
-        sc.add_root_node(<T as MyPoint>::random(&mut rng));
+        sc.add_root_node(<T as MyPoint>::random(&mut rng)).unwrap();
     }
     */
 
@@ -57,7 +65,7 @@ fn run<T, F>(config: &Config)
 
         // For each target node, add a root node.
         // XXX: Treat target and root nodes equally.
-        let root_idx = sc.add_root_node(target_pt); 
+        let root_idx = sc.add_root_node(target_pt).unwrap(); 
 
         // place n attractor points around the target_pt
         for _ in 0 .. config.attractors_per_target_node {
@@ -131,9 +139,9 @@ fn run<T, F>(config: &Config)
         });
         */
 
-        let new_nodes = sc.next();
+        let report = sc.next();
 
-        //println!("Iteration: {}. New nodes: {:?}", i, new_nodes);
+        //println!("Iteration: {}. {:?}", i, report);
 
         i += 1;
     }
@@ -8,27 +8,29 @@ extern crate clap;
 use kiss3d::window::Window;
 use na::{Pnt2, Pnt3, Vec2, Vec3, FloatPnt, FloatVec};
 use num::Zero;
-use space_colonization::{SpaceColonization, SqDist};
+use space_colonization::{SpaceColonization, SpaceColonizationBuilder, Coords};
 use common::{MyPoint, Config};
 use std::fmt::Debug;
 
 pub mod common;
 
 fn run<T, F>(config: &Config)
-    where T: MyPoint + FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug
+    where T: MyPoint + FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug
 {
     let mut rng = rand::thread_rng();
 
     let mut sc: SpaceColonization<T, F, ()> =
-        SpaceColonization::new(SqDist::from_dist(config.influence_radius),
-                               SqDist::from_dist(config.kill_distance),
-                               config.max_length,
-                               config.max_branches,
-                               config.move_distance);
+        SpaceColonizationBuilder::new(config.influence_radius,
+                                      config.kill_distance,
+                                      config.move_distance)
+            .max_length(config.max_length)
+            .max_branches(config.max_branches)
+            .build()
+            .unwrap();
 
     for _ in 0..config.n_roots {
-        sc.add_root_node(<T as MyPoint>::random(&mut rng));
+        sc.add_root_node(<T as MyPoint>::random(&mut rng)).unwrap();
     }
 
     for _ in 0..config.n_attraction_points {
@@ -62,9 +64,9 @@ fn run<T, F>(config: &Config)
             window.draw_line(&a.into_pnt3(), &b.into_pnt3(), &red)
         });
 
-        let new_nodes = sc.next();
+        let report = sc.next();
 
-        println!("Iteration: {}. New nodes: {:?}", i, new_nodes);
+        println!("Iteration: {}. {:?}", i, report);
 
         i += 1;
     }
@@ -0,0 +1,112 @@
+use na::{FloatPnt, FloatVec};
+use num::Zero;
+use std::fmt::Debug;
+use {ColonizationError, ConnectPolicy, Coords, InformationMerge, NodeSearch, SpaceColonization,
+     SqDist, Venation, ZeroGrowth};
+
+/// Validates the parameters of a ```SpaceColonization``` before
+/// creating it.
+#[derive(Debug, Copy, Clone)]
+pub struct SpaceColonizationBuilder {
+    attract_dist: f32,
+    connect_dist: f32,
+    move_dist: f32,
+    max_length: u32,
+    max_branches: u32,
+    node_search: NodeSearch,
+    connect_policy: ConnectPolicy,
+    venation: Venation,
+    zero_growth: ZeroGrowth,
+}
+
+impl SpaceColonizationBuilder {
+    /// Starts with a ```max_length``` of 100 and ```max_branches``` of 10.
+    /// The distances are not squared.
+    pub fn new(attract_dist: f32, connect_dist: f32, move_dist: f32) -> SpaceColonizationBuilder {
+        SpaceColonizationBuilder {
+            attract_dist: attract_dist,
+            connect_dist: connect_dist,
+            move_dist: move_dist,
+            max_length: 100,
+            max_branches: 10,
+            node_search: NodeSearch::Grid,
+            connect_policy: ConnectPolicy::FirstFound,
+            venation: Venation::Open,
+            zero_growth: ZeroGrowth::Skip,
+        }
+    }
+
+    pub fn max_length(mut self, max_length: u32) -> SpaceColonizationBuilder {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn max_branches(mut self, max_branches: u32) -> SpaceColonizationBuilder {
+        self.max_branches = max_branches;
+        self
+    }
+
+    pub fn node_search(mut self, node_search: NodeSearch) -> SpaceColonizationBuilder {
+        self.node_search = node_search;
+        self
+    }
+
+    pub fn connect_policy(mut self, connect_policy: ConnectPolicy) -> SpaceColonizationBuilder {
+        self.connect_policy = connect_policy;
+        self
+    }
+
+    pub fn venation(mut self, venation: Venation) -> SpaceColonizationBuilder {
+        self.venation = venation;
+        self
+    }
+
+    pub fn zero_growth(mut self, zero_growth: ZeroGrowth) -> SpaceColonizationBuilder {
+        self.zero_growth = zero_growth;
+        self
+    }
+
+    /// Checks the parameters without building.
+    pub fn validate(&self) -> Result<(), ColonizationError> {
+        if !(self.attract_dist > 0.0 && self.attract_dist.is_finite()) {
+            return Err(ColonizationError::InvalidAttractDist(self.attract_dist));
+        }
+        if !(self.connect_dist >= 0.0 && self.connect_dist.is_finite()) {
+            return Err(ColonizationError::InvalidConnectDist(self.connect_dist));
+        }
+        if self.connect_dist > self.attract_dist {
+            return Err(ColonizationError::ConnectDistExceedsAttractDist {
+                connect_dist: self.connect_dist,
+                attract_dist: self.attract_dist,
+            });
+        }
+        if !(self.move_dist > 0.0 && self.move_dist.is_finite()) {
+            return Err(ColonizationError::InvalidMoveDist(self.move_dist));
+        }
+        if self.max_length == 0 {
+            return Err(ColonizationError::ZeroMaxLength);
+        }
+        if self.max_branches == 0 {
+            return Err(ColonizationError::ZeroMaxBranches);
+        }
+        Ok(())
+    }
+
+    pub fn build<P, F, I>(&self) -> Result<SpaceColonization<P, F, I>, ColonizationError>
+        where P: FloatPnt<f32, F> + Coords + Debug,
+              F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+              I: InformationMerge + Default + Debug
+    {
+        self.validate()?;
+        let mut sc = SpaceColonization::new(SqDist::from_dist(self.attract_dist),
+                                            SqDist::from_dist(self.connect_dist),
+                                            self.max_length,
+                                            self.max_branches,
+                                            self.move_dist);
+        sc.set_node_search(self.node_search);
+        sc.set_connect_policy(self.connect_policy);
+        sc.set_venation(self.venation);
+        sc.set_zero_growth(self.zero_growth);
+        Ok(sc)
+    }
+}
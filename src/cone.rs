@@ -0,0 +1,47 @@
+use na::FloatVec;
+use coords::Coords;
+
+/// Rotates ```direction``` towards ```axis``` until the angle between them
+/// is at most ```half_angle``` radians. Both vectors must be normalized.
+pub fn clamp_to_cone<F>(direction: F, axis: F, half_angle: f32) -> F
+    where F: FloatVec<f32> + Coords + Copy
+{
+    let cos = direction.dot(&axis);
+    if cos >= half_angle.cos() {
+        return direction;
+    }
+
+    // The part of ```direction``` orthogonal to the axis.
+    let mut orthogonal = direction - axis * cos;
+    if orthogonal.sqnorm() <= 1.0e-12 {
+        // ```direction``` points exactly opposite to the axis.
+        orthogonal = any_orthogonal(axis);
+    }
+
+    axis * half_angle.cos() + orthogonal.normalize() * half_angle.sin()
+}
+
+/// Returns some vector orthogonal to the normalized ```axis```.
+fn any_orthogonal<F>(axis: F) -> F
+    where F: FloatVec<f32> + Coords + Copy
+{
+    // Project out the axis from the unit vector it is least aligned with.
+    let dim = F::dimensions();
+    let least = (0..dim)
+                    .min_by(|&a, &b| {
+                        axis.coord(a).abs().partial_cmp(&axis.coord(b).abs()).unwrap()
+                    })
+                    .unwrap();
+    let mut coords = [0.0; 3];
+    coords[least] = 1.0;
+    let unit = F::from_coords(&coords[..dim]);
+    unit - axis * unit.dot(&axis)
+}
+
+/// Returns true if the angle between ```direction``` and ```axis``` is at
+/// most ```half_angle``` radians. Both vectors must be normalized.
+pub fn within_cone<F>(direction: F, axis: F, half_angle: f32) -> bool
+    where F: FloatVec<f32> + Copy
+{
+    direction.dot(&axis) >= half_angle.cos()
+}
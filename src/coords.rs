@@ -0,0 +1,65 @@
+use na::{Pnt2, Pnt3, Vec2, Vec3};
+
+/// Access to the individual coordinates of the point and vector
+/// types a simulation operates on.
+pub trait Coords: Copy {
+    /// The number of dimensions of the space.
+    fn dimensions() -> usize;
+
+    /// The coordinate along ```axis``` (0 = x, 1 = y, 2 = z).
+    fn coord(&self, axis: usize) -> f32;
+
+    /// Constructs a value from the first ```dimensions()``` values
+    /// of ```coords```.
+    fn from_coords(coords: &[f32]) -> Self;
+}
+
+macro_rules! impl_coords_2 {
+    ($t:ident) => {
+        impl Coords for $t<f32> {
+            fn dimensions() -> usize {
+                2
+            }
+
+            fn coord(&self, axis: usize) -> f32 {
+                match axis {
+                    0 => self.x,
+                    1 => self.y,
+                    _ => panic!("invalid axis {}", axis),
+                }
+            }
+
+            fn from_coords(coords: &[f32]) -> $t<f32> {
+                $t::new(coords[0], coords[1])
+            }
+        }
+    }
+}
+
+macro_rules! impl_coords_3 {
+    ($t:ident) => {
+        impl Coords for $t<f32> {
+            fn dimensions() -> usize {
+                3
+            }
+
+            fn coord(&self, axis: usize) -> f32 {
+                match axis {
+                    0 => self.x,
+                    1 => self.y,
+                    2 => self.z,
+                    _ => panic!("invalid axis {}", axis),
+                }
+            }
+
+            fn from_coords(coords: &[f32]) -> $t<f32> {
+                $t::new(coords[0], coords[1], coords[2])
+            }
+        }
+    }
+}
+
+impl_coords_2!(Pnt2);
+impl_coords_2!(Vec2);
+impl_coords_3!(Pnt3);
+impl_coords_3!(Vec3);
@@ -0,0 +1,409 @@
+//! Attractor placement driven by grayscale density maps.
+//!
+//! A density map is a 2D image, or a stack of equally sized images
+//! forming a 3D volume. Attractors are placed with a probability
+//! proportional to the intensity of the pixel (voxel) they fall in.
+//!
+//! PGM images are always supported, PNG images with the ```png``` feature.
+
+use na::{FloatPnt, FloatVec};
+use num::Zero;
+use rand::Rng;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use load::LoadError;
+#[cfg(feature = "png")]
+use png;
+use {Coords, InformationMerge, SpaceColonization};
+
+/// Intensities in the range [0, 1], stored row by row, slice by slice.
+#[derive(Debug, Clone)]
+pub struct DensityMap {
+    width: usize,
+    height: usize,
+    depth: usize,
+    values: Vec<f32>,
+}
+
+impl DensityMap {
+    /// Returns an error if ```values``` does not hold exactly
+    /// ```width * height * depth``` intensities, or if one of them is
+    /// not finite.
+    pub fn from_values(width: usize,
+                       height: usize,
+                       depth: usize,
+                       values: Vec<f32>)
+                       -> Result<DensityMap, LoadError> {
+        let n = match width.checked_mul(height).and_then(|n| n.checked_mul(depth)) {
+            Some(n) => n,
+            None => {
+                return Err(LoadError::Parse(format!("size {}x{}x{} is too large",
+                                                    width,
+                                                    height,
+                                                    depth)))
+            }
+        };
+        if values.len() != n {
+            return Err(LoadError::Parse(format!("expected {} values, got {}", n, values.len())));
+        }
+        if let Some(value) = values.iter().find(|value| !value.is_finite()) {
+            return Err(LoadError::Parse(format!("intensity {} is not finite", value)));
+        }
+        Ok(DensityMap {
+            width: width,
+            height: height,
+            depth: depth,
+            values: values,
+        })
+    }
+
+    /// Stacks 2D maps of equal size into a 3D volume. The first map
+    /// becomes the slice at z = 0.
+    pub fn stack(slices: Vec<DensityMap>) -> Result<DensityMap, LoadError> {
+        let (width, height) = match slices.first() {
+            Some(first) => (first.width, first.height),
+            None => return Err(LoadError::Parse("no slices to stack".to_string())),
+        };
+        let mut values = Vec::with_capacity(width * height * slices.len());
+        let depth = slices.len();
+        for slice in slices {
+            if slice.width != width || slice.height != height || slice.depth != 1 {
+                return Err(LoadError::Parse(format!("slice of size {}x{}x{} does not match {}x{}",
+                                                    slice.width,
+                                                    slice.height,
+                                                    slice.depth,
+                                                    width,
+                                                    height)));
+            }
+            values.extend(slice.values);
+        }
+        DensityMap::from_values(width, height, depth, values)
+    }
+
+    /// Reads an ASCII (P2) or binary (P5) PGM image with 8 or 16 bits
+    /// per pixel.
+    pub fn read_pgm<R: Read>(reader: R) -> Result<DensityMap, LoadError> {
+        let bytes: Vec<u8> = reader.bytes().collect::<Result<_, _>>()?;
+        let mut pos = 0;
+        let magic = next_token(&bytes, &mut pos)?;
+        let width = parse_header(&next_token(&bytes, &mut pos)?)?;
+        let height = parse_header(&next_token(&bytes, &mut pos)?)?;
+        let max_value = parse_header(&next_token(&bytes, &mut pos)?)?;
+        if max_value == 0 || max_value > 65535 {
+            return Err(LoadError::Parse(format!("invalid maximum value {}", max_value)));
+        }
+
+        let n = match width.checked_mul(height) {
+            Some(n) => n,
+            None => return Err(LoadError::Parse(format!("size {}x{} is too large", width, height))),
+        };
+        let sample_size = if max_value < 256 {
+            1
+        } else {
+            2
+        };
+        // Every pixel takes at least one byte in both formats, which bounds
+        // the allocation below by the size of the input.
+        let data_size = match &magic[..] {
+            "P2" => Some(n),
+            "P5" => n.checked_mul(sample_size),
+            _ => return Err(LoadError::Unsupported(format!("PGM type {}", magic))),
+        };
+        match data_size {
+            Some(data_size) if data_size <= bytes.len().saturating_sub(pos + 1) => {}
+            _ => return Err(LoadError::Parse("truncated pixel data".to_string())),
+        }
+
+        let scale = 1.0 / max_value as f32;
+        let mut values = Vec::with_capacity(n);
+        match &magic[..] {
+            "P2" => {
+                for _ in 0..n {
+                    let value = parse_header(&next_token(&bytes, &mut pos)?)?;
+                    values.push(value as f32 * scale);
+                }
+            }
+            "P5" => {
+                // Exactly one whitespace character separates header and data.
+                pos += 1;
+                for i in 0..n {
+                    let value = if sample_size == 1 {
+                        bytes[pos + i] as u32
+                    } else {
+                        (bytes[pos + 2 * i] as u32) << 8 | bytes[pos + 2 * i + 1] as u32
+                    };
+                    values.push(value as f32 * scale);
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        DensityMap::from_values(width, height, 1, values)
+    }
+
+    pub fn open_pgm<P: AsRef<Path>>(path: P) -> Result<DensityMap, LoadError> {
+        DensityMap::read_pgm(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a grayscale PNG image (with or without alpha channel, which
+    /// is ignored) of any bit depth.
+    #[cfg(feature = "png")]
+    pub fn read_png<R: Read>(reader: R) -> Result<DensityMap, LoadError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let channels = match info.color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            other => return Err(LoadError::Unsupported(format!("PNG color type {:?}", other))),
+        };
+        let (width, height) = (info.width as usize, info.height as usize);
+        let mut values = Vec::with_capacity(width * height);
+        for row in buf.chunks(info.line_size).take(height) {
+            for x in 0..width {
+                let value = match info.bit_depth {
+                    png::BitDepth::Sixteen => {
+                        let i = 2 * channels * x;
+                        ((row[i] as u32) << 8 | row[i + 1] as u32) as f32 / 65535.0
+                    }
+                    _ => row[channels * x] as f32 / 255.0,
+                };
+                values.push(value);
+            }
+        }
+
+        DensityMap::from_values(width, height, 1, values)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn open_png<P: AsRef<Path>>(path: P) -> Result<DensityMap, LoadError> {
+        DensityMap::read_png(BufReader::new(File::open(path)?))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Number of slices. 1 for 2D maps.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The intensity of the pixel in column ```x```, row ```y``` and
+    /// slice ```z```.
+    pub fn density(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.values[(z * self.height + y) * self.width + x]
+    }
+
+    /// Returns ```n``` positions distributed proportionally to the
+    /// intensities, or none if all intensities are zero.
+    pub fn sample<P, R>(&self, mapping: &PixelMapping, n: usize, rng: &mut R) -> Vec<P>
+        where P: Coords,
+              R: Rng
+    {
+        // Cumulative intensities, searched by binary search.
+        let mut cumulative = Vec::with_capacity(self.values.len());
+        let mut total = 0.0f64;
+        for &value in self.values.iter() {
+            total += value.max(0.0) as f64;
+            cumulative.push(total);
+        }
+        if total <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut points = Vec::with_capacity(n);
+        for _ in 0..n {
+            let r = rng.gen::<f64>() * total;
+            let idx = match cumulative.binary_search_by(|c| c.partial_cmp(&r).unwrap()) {
+                Ok(idx) => idx + 1,
+                Err(idx) => idx,
+            };
+            let idx = if idx < cumulative.len() {
+                idx
+            } else {
+                cumulative.len() - 1
+            };
+
+            // Place the point anywhere within its pixel.
+            let x = (idx % self.width) as f32 + rng.gen::<f32>();
+            let y = (idx / self.width % self.height) as f32 + rng.gen::<f32>();
+            let z = (idx / (self.width * self.height)) as f32 + rng.gen::<f32>();
+            points.push(mapping.map([x, y, z]));
+        }
+        points
+    }
+}
+
+/// Maps pixel coordinates (column, row, slice) to the coordinate frame of
+/// the simulation: ```origin + pixel * scale```, per axis.
+#[derive(Debug, Copy, Clone)]
+pub struct PixelMapping {
+    pub origin: [f32; 3],
+    pub scale: [f32; 3],
+}
+
+impl PixelMapping {
+    pub fn new(origin: [f32; 3], scale: [f32; 3]) -> PixelMapping {
+        PixelMapping {
+            origin: origin,
+            scale: scale,
+        }
+    }
+
+    /// Maps the whole ```map``` onto the box from ```min``` to ```max```.
+    /// Images are stored top row first, so the first row maps to the
+    /// maximum y.
+    pub fn fit<P: Coords>(map: &DensityMap, min: &P, max: &P) -> PixelMapping {
+        let mut lo = [0.0; 3];
+        let mut hi = [0.0; 3];
+        for axis in 0..P::dimensions() {
+            lo[axis] = min.coord(axis);
+            hi[axis] = max.coord(axis);
+        }
+        let size = [map.width as f32, map.height as f32, map.depth as f32];
+        PixelMapping {
+            origin: [lo[0], hi[1], lo[2]],
+            scale: [(hi[0] - lo[0]) / size[0],
+                    (lo[1] - hi[1]) / size[1],
+                    (hi[2] - lo[2]) / size[2]],
+        }
+    }
+
+    pub fn map<P: Coords>(&self, pixel: [f32; 3]) -> P {
+        let mut coords = [0.0; 3];
+        for axis in 0..3 {
+            coords[axis] = self.origin[axis] + pixel[axis] * self.scale[axis];
+        }
+        P::from_coords(&coords[..P::dimensions()])
+    }
+}
+
+/// Adds ```n``` default attractors distributed according to ```map```.
+/// Returns the number of attractors added.
+pub fn add_attractors<P, F, I, R>(sc: &mut SpaceColonization<P, F, I>,
+                                  map: &DensityMap,
+                                  mapping: &PixelMapping,
+                                  n: usize,
+                                  rng: &mut R)
+                                  -> usize
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug,
+          R: Rng
+{
+    let points: Vec<P> = map.sample(mapping, n, rng);
+    let count = points.len();
+    for p in points {
+        sc.add_default_attractor(p);
+    }
+    count
+}
+
+fn is_space(b: u8) -> bool {
+    b == b' ' || b == b'\t' || b == b'\n' || b == b'\r'
+}
+
+/// Returns the next whitespace separated token, skipping comments.
+fn next_token(bytes: &[u8], pos: &mut usize) -> Result<String, LoadError> {
+    loop {
+        while *pos < bytes.len() && is_space(bytes[*pos]) {
+            *pos += 1;
+        }
+        if *pos < bytes.len() && bytes[*pos] == b'#' {
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let start = *pos;
+    while *pos < bytes.len() && !is_space(bytes[*pos]) {
+        *pos += 1;
+    }
+    if start == *pos {
+        return Err(LoadError::Parse("unexpected end of file".to_string()));
+    }
+    Ok(String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
+}
+
+fn parse_header(token: &str) -> Result<usize, LoadError> {
+    token.parse().map_err(|_| LoadError::Parse(format!("invalid number {:?}", token)))
+}
+
+#[cfg(test)]
+mod tests {
+    use load::LoadError;
+    use super::*;
+
+    /// Checks the size and the intensities of a 2D ```map```, row by row.
+    fn assert_map(map: &DensityMap, width: usize, expected: &[f32]) {
+        assert_eq!((map.width(), map.height(), map.depth()),
+                   (width, expected.len() / width, 1));
+        for (i, &value) in expected.iter().enumerate() {
+            let density = map.density(i % width, i / width, 0);
+            assert!((density - value).abs() < 1e-6,
+                    "pixel {}: {} != {}",
+                    i,
+                    density,
+                    value);
+        }
+    }
+
+    #[test]
+    fn reads_ascii_pgm() {
+        let data = "P2\n# a comment\n3 2\n255\n0 51 255\n102 0 255\n";
+        let map = DensityMap::read_pgm(data.as_bytes()).unwrap();
+        assert_map(&map, 3, &[0.0, 0.2, 1.0, 0.4, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn reads_ascii_pgm_with_16_bits() {
+        let map = DensityMap::read_pgm("P2 2 1 65535 0 65535".as_bytes()).unwrap();
+        assert_map(&map, 2, &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn reads_binary_pgm() {
+        let mut data = b"P5\n2 2\n255\n".to_vec();
+        data.extend_from_slice(&[0, 255, 51, 102]);
+        let map = DensityMap::read_pgm(&data[..]).unwrap();
+        assert_map(&map, 2, &[0.0, 1.0, 0.2, 0.4]);
+    }
+
+    #[test]
+    fn reads_binary_pgm_with_16_bits_big_endian() {
+        let mut data = b"P5 3 1 65535\n".to_vec();
+        data.extend_from_slice(&[0x00, 0x00, 0xff, 0xff, 0x33, 0x33]);
+        let map = DensityMap::read_pgm(&data[..]).unwrap();
+        assert_map(&map, 3, &[0.0, 1.0, 0.2]);
+    }
+
+    #[test]
+    fn rejects_truncated_binary_pgm() {
+        let mut data = b"P5\n2 2\n255\n".to_vec();
+        data.extend_from_slice(&[0, 255, 51]);
+        match DensityMap::read_pgm(&data[..]) {
+            Err(LoadError::Parse(_)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_color_pgm() {
+        match DensityMap::read_pgm("P3 1 1 255 0 0 0".as_bytes()) {
+            Err(LoadError::Unsupported(_)) => {}
+            other => panic!("expected an unsupported error, got {:?}", other),
+        }
+    }
+}
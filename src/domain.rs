@@ -0,0 +1,192 @@
+//! Domains that grow while the nodes develop, like the blade of a leaf.
+//!
+//! Between two calls to ```next```, ```GrowingDomain::grow``` moves all
+//! nodes and attractors along with the domain and places new attractors
+//! in the area that was added.
+
+use na::{FloatPnt, FloatVec};
+use num::Zero;
+use rand::Rng;
+use std::fmt::Debug;
+use sampling::{self, Envelope};
+use {Coords, InformationMerge, SpaceColonization};
+
+/// A region of space that changes from step to step.
+pub trait Domain<P> {
+    /// Returns true if ```position``` lies inside the shape after ```step```
+    /// growth steps.
+    fn contains(&self, step: u32, position: &P) -> bool;
+
+    /// Returns the minimum and maximum corner of an axis-aligned box
+    /// enclosing the shape after ```step``` growth steps.
+    fn bounds(&self, step: u32) -> (P, P);
+
+    /// Returns where the material at ```position``` moves when growing
+    /// from ```step``` to ```step + 1```.
+    fn displace(&self, step: u32, position: &P) -> P;
+}
+
+/// The shape of a ```Domain``` at one step.
+pub struct DomainShape<'a, D: 'a> {
+    pub domain: &'a D,
+    pub step: u32,
+}
+
+impl<'a, P, D> Envelope<P> for DomainShape<'a, D>
+    where D: Domain<P>
+{
+    fn contains(&self, position: &P) -> bool {
+        self.domain.contains(self.step, position)
+    }
+
+    fn bounds(&self) -> (P, P) {
+        self.domain.bounds(self.step)
+    }
+}
+
+/// Where the area of a ```ScaledDomain``` is added.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Expansion {
+    /// Everywhere. The contents are stretched with the shape.
+    Uniform,
+
+    /// At the margin only. The contents stay in place.
+    Marginal,
+}
+
+/// A shape scaled about ```center``` by the per-axis factors
+/// ```scale(step)```.
+pub struct ScaledDomain<P, E, S> {
+    pub shape: E,
+    pub center: P,
+    pub scale: S,
+    pub expansion: Expansion,
+}
+
+impl<P, E, S> ScaledDomain<P, E, S> {
+    pub fn new(shape: E, center: P, scale: S, expansion: Expansion) -> ScaledDomain<P, E, S> {
+        ScaledDomain {
+            shape: shape,
+            center: center,
+            scale: scale,
+            expansion: expansion,
+        }
+    }
+}
+
+/// Returns ```center + (position - center) * factor(axis)```.
+fn scale_about<P: Coords, G: Fn(usize) -> f32>(position: &P, center: &P, factor: G) -> P {
+    let mut coords = [0.0; 3];
+    for axis in 0..P::dimensions() {
+        coords[axis] = center.coord(axis) +
+                       (position.coord(axis) - center.coord(axis)) * factor(axis);
+    }
+    P::from_coords(&coords[..P::dimensions()])
+}
+
+impl<P, F, E, S> Domain<P> for ScaledDomain<P, E, S>
+    where P: Coords,
+          F: Coords,
+          E: Envelope<P>,
+          S: Fn(u32) -> F
+{
+    fn contains(&self, step: u32, position: &P) -> bool {
+        let scale = (self.scale)(step);
+        let unscaled = scale_about(position, &self.center, |axis| 1.0 / scale.coord(axis));
+        self.shape.contains(&unscaled)
+    }
+
+    fn bounds(&self, step: u32) -> (P, P) {
+        let scale = (self.scale)(step);
+        let (min, max) = self.shape.bounds();
+        (scale_about(&min, &self.center, |axis| scale.coord(axis)),
+         scale_about(&max, &self.center, |axis| scale.coord(axis)))
+    }
+
+    fn displace(&self, step: u32, position: &P) -> P {
+        match self.expansion {
+            Expansion::Uniform => {
+                let (from, to) = ((self.scale)(step), (self.scale)(step + 1));
+                scale_about(position,
+                            &self.center,
+                            |axis| to.coord(axis) / from.coord(axis))
+            }
+            Expansion::Marginal => *position,
+        }
+    }
+}
+
+/// Drives a ```Domain``` and keeps a simulation in sync with it.
+pub struct GrowingDomain<D> {
+    pub domain: D,
+
+    /// Minimum distance of new attractors to each other, and to the
+    /// attractors and nodes already present.
+    pub min_dist: f32,
+    step: u32,
+}
+
+impl<D> GrowingDomain<D> {
+    pub fn new(domain: D, min_dist: f32) -> GrowingDomain<D> {
+        GrowingDomain {
+            domain: domain,
+            min_dist: min_dist,
+            step: 0,
+        }
+    }
+
+    /// The number of growth steps taken.
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    /// The current shape.
+    pub fn shape<'a>(&'a self) -> DomainShape<'a, D> {
+        DomainShape {
+            domain: &self.domain,
+            step: self.step,
+        }
+    }
+
+    /// Adds attractors by Poisson-disk sampling wherever the current shape
+    /// has room for them. Returns the number of attractors added, which
+    /// is 0 unless ```min_dist``` is positive and finite.
+    pub fn populate<P, F, I, R>(&self, sc: &mut SpaceColonization<P, F, I>, rng: &mut R) -> usize
+        where P: FloatPnt<f32, F> + Coords + Debug,
+              F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+              I: InformationMerge + Default + Debug,
+              D: Domain<P>,
+              R: Rng
+    {
+        if !(self.min_dist > 0.0 && self.min_dist.is_finite()) {
+            return 0;
+        }
+        let mut existing = Vec::new();
+        existing.extend(sc.attractors().map(|a| a.position));
+        existing.extend(sc.nodes().map(|n| n.position));
+        let points = sampling::poisson_disk_around(&self.shape(), self.min_dist, &existing, rng);
+        let count = points.len();
+        for p in points {
+            sc.add_default_attractor(p);
+        }
+        count
+    }
+
+    /// Takes one growth step: moves the nodes and attractors of ```sc```
+    /// with the domain, then populates the added area. Returns the number
+    /// of attractors added.
+    pub fn grow<P, F, I, R>(&mut self, sc: &mut SpaceColonization<P, F, I>, rng: &mut R) -> usize
+        where P: FloatPnt<f32, F> + Coords + Debug,
+              F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+              I: InformationMerge + Default + Debug,
+              D: Domain<P>,
+              R: Rng
+    {
+        {
+            let (domain, step) = (&self.domain, self.step);
+            sc.transform_positions(|position| domain.displace(step, position));
+        }
+        self.step += 1;
+        self.populate(sc, rng)
+    }
+}
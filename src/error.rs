@@ -0,0 +1,69 @@
+use std::error::Error;
+use std::fmt;
+use NodeIdx;
+
+/// Error from building a ```SpaceColonization``` or from operating on
+/// one of its nodes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColonizationError {
+    /// The attract distance is zero, negative or not finite.
+    InvalidAttractDist(f32),
+
+    /// The connect distance is negative or not finite.
+    InvalidConnectDist(f32),
+
+    /// Nodes would connect with attractors before being attracted by them.
+    ConnectDistExceedsAttractDist {
+        connect_dist: f32,
+        attract_dist: f32,
+    },
+
+    /// The move distance is zero, negative or not finite.
+    InvalidMoveDist(f32),
+
+    /// No node could grow beyond its root.
+    ZeroMaxLength,
+
+    /// No node could grow a branch.
+    ZeroMaxBranches,
+
+    /// There is no node with this index.
+    InvalidNodeIdx(NodeIdx),
+
+    /// The node is not a root node.
+    NotARoot(NodeIdx),
+
+    /// The position of the node has a NaN or infinite coordinate.
+    NonFinitePosition(NodeIdx),
+}
+
+impl fmt::Display for ColonizationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColonizationError::InvalidAttractDist(d) => {
+                write!(f, "attract distance must be positive, got {}", d)
+            }
+            ColonizationError::InvalidConnectDist(d) => {
+                write!(f, "connect distance must not be negative, got {}", d)
+            }
+            ColonizationError::ConnectDistExceedsAttractDist { connect_dist, attract_dist } => {
+                write!(f,
+                       "connect distance {} exceeds attract distance {}",
+                       connect_dist,
+                       attract_dist)
+            }
+            ColonizationError::InvalidMoveDist(d) => {
+                write!(f, "move distance must be positive, got {}", d)
+            }
+            ColonizationError::ZeroMaxLength => write!(f, "max length must not be 0"),
+            ColonizationError::ZeroMaxBranches => write!(f, "max branches must not be 0"),
+            ColonizationError::InvalidNodeIdx(idx) => write!(f, "no node with index {}", idx.0),
+            ColonizationError::NotARoot(idx) => write!(f, "node {} is not a root node", idx.0),
+            ColonizationError::NonFinitePosition(idx) => {
+                write!(f, "node {} has a non-finite position", idx.0)
+            }
+        }
+    }
+}
+
+impl Error for ColonizationError {}
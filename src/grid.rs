@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use coords::Coords;
+
+/// Integer coordinates of a grid cell. Unused axes are zero.
+type Cell = [i32; 3];
+
+/// A uniform grid bucketing node indices by their position.
+///
+/// Every cell keeps its indices in insertion order.
+pub struct Grid {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(cell_size: f32) -> Grid {
+        Grid {
+            cell_size: if cell_size > 0.0 {
+                cell_size
+            } else {
+                1.0
+            },
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell_index(&self, x: f32) -> i32 {
+        (x / self.cell_size).floor() as i32
+    }
+
+    fn cell_of<P: Coords>(&self, position: &P) -> Cell {
+        let mut cell = [0; 3];
+        for axis in 0..P::dimensions() {
+            cell[axis] = self.cell_index(position.coord(axis));
+        }
+        cell
+    }
+
+    pub fn insert<P: Coords>(&mut self, position: &P, idx: usize) {
+        let cell = self.cell_of(position);
+        self.cells.entry(cell).or_insert_with(Vec::new).push(idx);
+    }
+
+    pub fn remove<P: Coords>(&mut self, position: &P, idx: usize) {
+        let cell = self.cell_of(position);
+        let now_empty = match self.cells.get_mut(&cell) {
+            Some(indices) => {
+                indices.retain(|&i| i != idx);
+                indices.is_empty()
+            }
+            None => false,
+        };
+        if now_empty {
+            self.cells.remove(&cell);
+        }
+    }
+
+    /// Calls ```visitor``` for every index stored in a cell that
+    /// intersects the cube with half-width ```radius``` centered at
+    /// ```position```. The order of the indices is unspecified.
+    pub fn visit_near<P, V>(&self, position: &P, radius: f32, visitor: &mut V)
+        where P: Coords,
+              V: FnMut(usize)
+    {
+        // Pad the radius a little so that rounding errors never exclude a
+        // cell containing a node that passes the exact distance test.
+        let radius = radius * 1.0001 + 1.0e-6;
+        let mut lo = [0; 3];
+        let mut hi = [0; 3];
+        for axis in 0..P::dimensions() {
+            let x = position.coord(axis);
+            lo[axis] = self.cell_index(x - radius);
+            hi[axis] = self.cell_index(x + radius);
+        }
+
+        // The cell indices saturate for huge radii, so the range can span
+        // more cells than an i32, or even a u64, can count.
+        let num_cells = (0..3).fold(Some(1u64), |n, axis| {
+            let span = (hi[axis] as i64 - lo[axis] as i64 + 1) as u64;
+            n.and_then(|n| n.checked_mul(span))
+        });
+        if num_cells.map_or(true, |n| n > self.cells.len() as u64) {
+            // Fewer occupied cells than cells in range. Scan the occupied ones.
+            for (cell, indices) in self.cells.iter() {
+                if (0..3).all(|axis| cell[axis] >= lo[axis] && cell[axis] <= hi[axis]) {
+                    for &idx in indices.iter() {
+                        visitor(idx);
+                    }
+                }
+            }
+            return;
+        }
+
+        for cx in lo[0]..=hi[0] {
+            for cy in lo[1]..=hi[1] {
+                for cz in lo[2]..=hi[2] {
+                    if let Some(indices) = self.cells.get(&[cx, cy, cz]) {
+                        for &idx in indices.iter() {
+                            visitor(idx);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
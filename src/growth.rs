@@ -0,0 +1,72 @@
+use na::{FloatPnt, FloatVec};
+use num::Zero;
+use std::fmt::Debug;
+use Node;
+
+/// Determines how far, and in which direction, a node grows.
+pub trait GrowthStep<P, F, I>
+    where P: Debug,
+          F: Debug,
+          I: Clone + Debug
+{
+    /// Returns the displacement of the new node from ```node```.
+    /// ```growth``` is the sum of the pulls of the ```growth_count```
+    /// attractors that influence ```node```.
+    fn step(&self, node: &Node<P, F, I>, growth: F, growth_count: u32) -> F;
+}
+
+impl<P, F, I, T> GrowthStep<P, F, I> for T
+    where P: Debug,
+          F: Debug,
+          I: Clone + Debug,
+          T: Fn(&Node<P, F, I>, F, u32) -> F
+{
+    fn step(&self, node: &Node<P, F, I>, growth: F, growth_count: u32) -> F {
+        self(node, growth, growth_count)
+    }
+}
+
+/// Steps of constant length.
+#[derive(Debug, Copy, Clone)]
+pub struct ConstantStep(pub f32);
+
+impl<P, F, I> GrowthStep<P, F, I> for ConstantStep
+    where P: FloatPnt<f32, F> + Debug,
+          F: FloatVec<f32> + Zero + Copy + Debug,
+          I: Clone + Debug
+{
+    fn step(&self, _node: &Node<P, F, I>, growth: F, _growth_count: u32) -> F {
+        growth.normalize() * self.0
+    }
+}
+
+/// Steps whose length grows with the logarithm of the number of
+/// attractors: ```length * ln(growth_count + 1)```.
+#[derive(Debug, Copy, Clone)]
+pub struct LogarithmicStep(pub f32);
+
+impl<P, F, I> GrowthStep<P, F, I> for LogarithmicStep
+    where P: FloatPnt<f32, F> + Debug,
+          F: FloatVec<f32> + Zero + Copy + Debug,
+          I: Clone + Debug
+{
+    fn step(&self, _node: &Node<P, F, I>, growth: F, growth_count: u32) -> F {
+        let growth_factor = ((growth_count + 1) as f32).ln();
+        growth.normalize() * self.0 * growth_factor
+    }
+}
+
+/// Steps whose length is proportional to the magnitude of the summed
+/// attractor forces: ```factor * |growth|```.
+#[derive(Debug, Copy, Clone)]
+pub struct ForceProportionalStep(pub f32);
+
+impl<P, F, I> GrowthStep<P, F, I> for ForceProportionalStep
+    where P: FloatPnt<f32, F> + Debug,
+          F: FloatVec<f32> + Zero + Copy + Debug,
+          I: Clone + Debug
+{
+    fn step(&self, _node: &Node<P, F, I>, growth: F, _growth_count: u32) -> F {
+        growth * self.0
+    }
+}
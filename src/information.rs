@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+/// Combines the information a node holds with information it receives
+/// from another attractor.
+///
+/// It is implemented for the primitive types, ```Option```, tuples and
+/// arrays, which all keep the incoming information. Other types from
+/// outside this crate, e.g. ```String``` or ```Vec```, must be wrapped in
+/// ```KeepLast```, ```KeepFirst``` or a type of your own.
+pub trait InformationMerge: Clone {
+    /// Merges ```incoming``` into ```self```. Keeps the incoming
+    /// information by default.
+    fn merge(&mut self, incoming: &Self) {
+        *self = incoming.clone();
+    }
+}
+
+impl InformationMerge for () {}
+
+macro_rules! impl_keep_incoming {
+    ($($t:ty),*) => {
+        $(impl InformationMerge for $t {})*
+    }
+}
+
+impl_keep_incoming!(bool, char, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+impl<T: Clone> InformationMerge for Option<T> {}
+
+macro_rules! impl_keep_incoming_tuple {
+    ($(($($t:ident),+)),*) => {
+        $(impl<$($t: Clone),+> InformationMerge for ($($t,)+) {})*
+    }
+}
+
+impl_keep_incoming_tuple!((A),
+                          (A, B),
+                          (A, B, C),
+                          (A, B, C, D),
+                          (A, B, C, D, E),
+                          (A, B, C, D, E, G),
+                          (A, B, C, D, E, G, H),
+                          (A, B, C, D, E, G, H, J),
+                          (A, B, C, D, E, G, H, J, K),
+                          (A, B, C, D, E, G, H, J, K, L),
+                          (A, B, C, D, E, G, H, J, K, L, M),
+                          (A, B, C, D, E, G, H, J, K, L, M, N));
+
+impl<T: Clone, const N: usize> InformationMerge for [T; N] {}
+
+/// Keeps the most recently received information.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KeepLast<T>(pub T);
+
+impl<T: Clone> InformationMerge for KeepLast<T> {}
+
+/// Keeps the information received first.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KeepFirst<T>(pub T);
+
+impl<T: Clone> InformationMerge for KeepFirst<T> {
+    fn merge(&mut self, _incoming: &Self) {}
+}
+
+/// Collects all received values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct InformationSet<T: Ord>(pub BTreeSet<T>);
+
+impl<T: Ord> InformationSet<T> {
+    /// A set holding just ```value```, as carried by an attractor.
+    pub fn single(value: T) -> InformationSet<T> {
+        let mut set = BTreeSet::new();
+        set.insert(value);
+        InformationSet(set)
+    }
+}
+
+impl<T: Ord + Clone> InformationMerge for InformationSet<T> {
+    fn merge(&mut self, incoming: &Self) {
+        self.0.extend(incoming.0.iter().cloned());
+    }
+}
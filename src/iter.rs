@@ -0,0 +1,229 @@
+use std::fmt::Debug;
+use std::iter::Enumerate;
+use std::ops::Deref;
+use std::slice;
+use {Attractor, Node, NodeIdx};
+
+/// A node together with its index.
+#[derive(Debug)]
+pub struct NodeRef<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    pub idx: NodeIdx,
+    pub node: &'a Node<P, F, I>,
+}
+
+impl<'a, P, F, I> Clone for NodeRef<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, P, F, I> Copy for NodeRef<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+}
+
+impl<'a, P, F, I> Deref for NodeRef<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    type Target = Node<P, F, I>;
+
+    fn deref(&self) -> &Node<P, F, I> {
+        self.node
+    }
+}
+
+/// A branch segment from a node to its direct parent or to one of its
+/// extra parents.
+#[derive(Debug)]
+pub struct Segment<'a, P: 'a> {
+    pub node: NodeIdx,
+    pub parent: NodeIdx,
+    pub position: &'a P,
+    pub parent_position: &'a P,
+}
+
+impl<'a, P: 'a> Clone for Segment<'a, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, P: 'a> Copy for Segment<'a, P> {}
+
+/// Iterates over all nodes in the order they were added.
+pub struct Nodes<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    iter: Enumerate<slice::Iter<'a, Node<P, F, I>>>,
+}
+
+impl<'a, P, F, I> Nodes<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    pub fn new(nodes: &'a [Node<P, F, I>]) -> Nodes<'a, P, F, I> {
+        Nodes { iter: nodes.iter().enumerate() }
+    }
+}
+
+impl<'a, P, F, I> Iterator for Nodes<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    type Item = NodeRef<'a, P, F, I>;
+
+    fn next(&mut self) -> Option<NodeRef<'a, P, F, I>> {
+        self.iter.next().map(|(i, node)| {
+            NodeRef {
+                idx: NodeIdx(i as u32),
+                node: node,
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Iterates over the nodes for which ```accept``` returns true.
+pub struct FilterNodes<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    nodes: Nodes<'a, P, F, I>,
+    accept: fn(&Node<P, F, I>) -> bool,
+}
+
+impl<'a, P, F, I> FilterNodes<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    pub fn new(nodes: &'a [Node<P, F, I>],
+               accept: fn(&Node<P, F, I>) -> bool)
+               -> FilterNodes<'a, P, F, I> {
+        FilterNodes {
+            nodes: Nodes::new(nodes),
+            accept: accept,
+        }
+    }
+}
+
+impl<'a, P, F, I> Iterator for FilterNodes<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    type Item = NodeRef<'a, P, F, I>;
+
+    fn next(&mut self) -> Option<NodeRef<'a, P, F, I>> {
+        let accept = self.accept;
+        self.nodes.find(|n| accept(n.node))
+    }
+}
+
+/// Iterates over the segments of all nodes. The segment to the direct
+/// parent comes before those to the extra parents.
+pub struct Segments<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    nodes: &'a [Node<P, F, I>],
+    node: usize,
+
+    /// The next parent of ```node```. 0 is the direct parent, above
+    /// are the extra parents.
+    parent: usize,
+}
+
+impl<'a, P, F, I> Segments<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    pub fn new(nodes: &'a [Node<P, F, I>]) -> Segments<'a, P, F, I> {
+        Segments {
+            nodes: nodes,
+            node: 0,
+            parent: 0,
+        }
+    }
+}
+
+impl<'a, P, F, I> Iterator for Segments<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    type Item = Segment<'a, P>;
+
+    fn next(&mut self) -> Option<Segment<'a, P>> {
+        let nodes = self.nodes;
+        while let Some(node) = nodes.get(self.node) {
+            let idx = NodeIdx(self.node as u32);
+            let parent = if self.parent == 0 {
+                node.parent()
+            } else {
+                Some(node.extra_parents[self.parent - 1])
+            };
+            if self.parent >= node.extra_parents.len() {
+                self.node += 1;
+                self.parent = 0;
+            } else {
+                self.parent += 1;
+            }
+            if let Some(parent) = parent {
+                return Some(Segment {
+                    node: idx,
+                    parent: parent,
+                    position: &node.position,
+                    parent_position: &nodes[parent.0 as usize].position,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Iterates over the attractors that are active in an iteration.
+pub struct ActiveAttractors<'a, P: 'a, I: Clone + 'a> {
+    iter: slice::Iter<'a, Attractor<P, I>>,
+    iteration: u32,
+}
+
+impl<'a, P: 'a, I: Clone + 'a> ActiveAttractors<'a, P, I> {
+    pub fn new(attractors: &'a [Attractor<P, I>], iteration: u32) -> ActiveAttractors<'a, P, I> {
+        ActiveAttractors {
+            iter: attractors.iter(),
+            iteration: iteration,
+        }
+    }
+}
+
+impl<'a, P: 'a, I: Clone + 'a> Iterator for ActiveAttractors<'a, P, I> {
+    type Item = &'a Attractor<P, I>;
+
+    fn next(&mut self) -> Option<&'a Attractor<P, I>> {
+        let iteration = self.iteration;
+        self.iter.find(|a| a.is_active_in(iteration))
+    }
+}
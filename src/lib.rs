@@ -1,10 +1,55 @@
 extern crate nalgebra as na;
 extern crate num;
+extern crate rand;
+#[cfg(feature = "png")]
+extern crate png;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 
 use na::{Norm, FloatPnt, FloatVec};
 use num::Zero;
-use std::cmp;
+use rand::{SeedableRng, XorShiftRng};
+use std::cmp::{self, Ordering};
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::slice;
+use grid::Grid;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub use builder::SpaceColonizationBuilder;
+pub use coords::Coords;
+pub use error::ColonizationError;
+pub use load::LoadError;
+pub use information::{InformationMerge, KeepFirst, KeepLast, InformationSet};
+pub use growth::{GrowthStep, ConstantStep, LogarithmicStep, ForceProportionalStep};
+pub use obstacle::{Obstacle, ObstacleResponse, SphereObstacle, BoxObstacle, HalfSpaceObstacle,
+                   PolygonObstacle};
+pub use perception::Perception;
+pub use tropism::Tropism;
+pub use topology::{DepthFirst, BreadthFirst, PathToRoot};
+pub use iter::{NodeRef, Segment, Nodes, FilterNodes, Segments, ActiveAttractors};
+
+mod builder;
+mod cone;
+mod coords;
+pub mod density;
+pub mod domain;
+mod error;
+mod grid;
+mod growth;
+mod information;
+mod iter;
+mod load;
+pub mod mesh;
+pub mod network;
+mod obstacle;
+mod perception;
+pub mod pointcloud;
+pub mod sampling;
+mod segment;
+mod topology;
+mod tropism;
 
 /// Wraps a square distance.
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
@@ -26,8 +71,86 @@ pub enum ConnectAction {
     DisableForConnectingRoot,
 }
 
+/// How ```next``` finds the nodes that are influenced by an attractor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NodeSearch {
+    /// Test every node against every attractor.
+    BruteForce,
+
+    /// Look up nodes in a uniform grid whose cell size is the
+    /// default attract distance. Grows exactly the same nodes as
+    /// ```BruteForce```, only faster.
+    Grid,
+}
+
+/// Which of the nodes within the connect distance of an attractor
+/// connect with it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConnectPolicy {
+    /// The node that was added first. This favors older trees.
+    FirstFound,
+
+    /// The closest node.
+    Nearest,
+
+    /// All nodes. Each of them receives the attractor's information.
+    /// The ```connect_action``` is performed once, with the closest node
+    /// being the connecting one.
+    AllWithinRadius,
+}
+
+/// How attractors choose the nodes they attract (Runions et al. 2005).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Venation {
+    /// An attractor attracts its nearest node and performs its
+    /// ```connect_action``` as soon as a node connects. Grows trees.
+    Open,
+
+    /// An attractor attracts all nodes in its relative neighborhood: a
+    /// node is in it unless another node is closer to both the attractor
+    /// and the node. The ```connect_action``` is performed once all of
+    /// these nodes connect, and the nearest of them becomes their common
+    /// child, which closes loops. The ```ConnectPolicy``` is ignored.
+    Closed,
+}
+
+/// What a node does whose growth direction vanishes, because the pulls
+/// of its attractors cancel out or an attractor sits exactly on it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ZeroGrowth {
+    /// The node is not grown.
+    Skip,
+
+    /// The node grows in a random direction. The random numbers are
+    /// taken from the simulation's own generator, see ```reseed```.
+    RandomDirection,
+
+    /// The node continues the direction of its parent segment. Root
+    /// nodes are not grown.
+    ParentDirection,
+}
+
+/// What blocks the line of sight between an attractor and a node. An
+/// attractor only attracts nodes it can see.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Occlusion {
+    /// Nothing, attractors see through everything.
+    Disabled,
+
+    /// The obstacles.
+    Obstacles,
+
+    /// The obstacles and all branch segments that come closer than
+    /// ```radius``` to the line of sight. Segments ending in the attracted
+    /// node are ignored, so ```radius``` should be well below the length of
+    /// a segment.
+    ObstaclesAndBranches {
+        radius: f32,
+    },
+}
+
 #[derive(Debug, Copy, Clone)]
-pub struct Attractor<P, I: Copy> {
+pub struct Attractor<P, I: Clone> {
     /// The square distance within which it can influence a Node.
     pub attract_dist: SqDist,
 
@@ -46,7 +169,7 @@ pub struct Attractor<P, I: Copy> {
 
     /// The attractor carries a bit of information.
     /// When a node comes closer than ```connect_radius```
-    /// this bit of information is exchanged.
+    /// this bit of information is merged into the node's.
     pub information: I,
 
     /// Action performed when a node comes closer
@@ -66,7 +189,7 @@ pub struct Attractor<P, I: Copy> {
     pub not_for_connecting_root: Option<NodeIdx>,
 }
 
-impl<P, I: Copy> Attractor<P, I> {
+impl<P, I: Clone> Attractor<P, I> {
     fn is_active_in(&self, current_iteration: u32) -> bool {
         current_iteration >= self.active_from_iteration
     }
@@ -74,20 +197,56 @@ impl<P, I: Copy> Attractor<P, I> {
     fn disable_until(&mut self, iteration: u32) {
         self.active_from_iteration = iteration;
     }
+
+    fn accepts_root(&self, root: NodeIdx) -> bool {
+        self.not_for_root != Some(root) && self.not_for_connecting_root != Some(root)
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct NodeIdx(pub u32);
 
+/// An edge joining two trees, created when a new node of one tree comes
+/// within the merge distance of a node of another (anastomosis).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Junction {
+    /// The new node.
+    pub node: NodeIdx,
+
+    /// The node of the other tree, which became an extra parent of ```node```.
+    pub other: NodeIdx,
+
+    /// The root of ```node```.
+    pub root: NodeIdx,
+
+    /// The root of ```other```.
+    pub other_root: NodeIdx,
+}
+
+impl Junction {
+    /// Returns true if the junction joins the trees rooted at ```a``` and ```b```.
+    pub fn joins(&self, a: NodeIdx, b: NodeIdx) -> bool {
+        (self.root == a && self.other_root == b) || (self.root == b && self.other_root == a)
+    }
+}
+
 #[derive(Debug)]
 pub struct Node<P, F, I>
     where P: Debug,
           F: Debug,
-          I: Copy + Debug
+          I: Clone + Debug
 {
     /// Index of the direct parent.
     parent: NodeIdx,
 
+    /// Further parents, joined to this node by ```Venation::Closed``` or
+    /// anastomosis.
+    extra_parents: Vec<NodeIdx>,
+
+    /// Nodes that have this node as direct parent, in the order they
+    /// were added.
+    children: Vec<NodeIdx>,
+
     /// Index of the root node this node is associated with.
     pub root: NodeIdx,
 
@@ -116,10 +275,26 @@ pub struct Node<P, F, I>
 impl<P, F, I> Node<P, F, I>
     where P: Debug,
           F: Debug,
-          I: Copy + Debug
+          I: Clone + Debug
 {
-    fn transmit_information(&mut self, information: I) {
-        self.assigned_information = Some(information);
+    /// The parents besides the direct parent. Only closed venation and
+    /// anastomosis create them.
+    pub fn extra_parents(&self) -> &[NodeIdx] {
+        &self.extra_parents
+    }
+
+    /// The direct parent, or ```None``` for root nodes.
+    pub fn parent(&self) -> Option<NodeIdx> {
+        if self.length == 0 {
+            None
+        } else {
+            Some(self.parent)
+        }
+    }
+
+    /// The nodes that have this node as direct parent.
+    pub fn children(&self) -> &[NodeIdx] {
+        &self.children
     }
 
     pub fn is_leaf(&self) -> bool {
@@ -140,27 +315,125 @@ impl<P, F, I> Node<P, F, I>
     }
 }
 
+impl<P, F, I> Node<P, F, I>
+    where P: Debug,
+          F: Debug,
+          I: InformationMerge + Debug
+{
+    fn transmit_information(&mut self, information: &I) {
+        match self.assigned_information {
+            Some(ref mut assigned) => assigned.merge(information),
+            None => self.assigned_information = Some(information.clone()),
+        }
+    }
+}
+
+/// Summary of what happened in one call to ```next```.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IterationReport {
+    /// The iteration this report is about, starting at 0.
+    pub iteration: u32,
+
+    /// Number of nodes grown.
+    pub new_nodes: usize,
+
+    /// Number of nodes not grown because they would have entered an obstacle.
+    pub blocked_nodes: usize,
+
+    /// Number of nodes whose growth direction vanished. They are handled
+    /// according to the ```ZeroGrowth``` setting.
+    pub zero_growth_nodes: usize,
+
+    /// Number of attractors removed by ```ConnectAction::KillAttractor```.
+    pub killed_attractors: usize,
+
+    /// Number of attractors disabled by ```ConnectAction::DisableFor```
+    /// or ```ConnectAction::DisableForConnectingRoot```.
+    pub disabled_attractors: usize,
+
+    /// Number of times an attractor transmitted its information to a node.
+    pub information_transmissions: usize,
+
+    /// Number of extra parents joined to nodes by ```Venation::Closed```.
+    pub joined_nodes: usize,
+
+    /// Number of junctions created between trees.
+    pub new_junctions: usize,
+
+    /// Number of nodes that can still grow.
+    pub active_nodes: usize,
+
+    /// Number of attractors that are active in the next iteration.
+    pub active_attractors: usize,
+}
+
+/// The nodes an attractor acts upon in one iteration.
+struct Influence {
+    /// The nodes that connect with the attractor, nearest first.
+    connect: Vec<usize>,
+
+    /// The nodes pulled towards the attractor.
+    attracted: Vec<usize>,
+}
+
 pub struct SpaceColonization<P, F, I>
-    where P: FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug,
-          I: Copy + Default + Debug
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug
 {
     nodes: Vec<Node<P, F, I>>,
     attractors: Vec<Attractor<P, I>>,
     default_attract_dist: SqDist,
     default_connect_dist: SqDist,
-    move_dist: f32,
+    growth_step: Box<dyn GrowthStep<P, F, I> + Send>,
+
+    /// Applies to all trees without an entry in ```root_tropisms```.
+    tropism: Option<Tropism<F>>,
+    root_tropisms: HashMap<NodeIdx, Tropism<F>>,
+
+    /// A spatially varying pull and its weight.
+    tropism_field: Option<(Box<dyn Fn(&P) -> F + Send>, f32)>,
+
+    /// Weight of the parent segment's direction in the growth direction.
+    inertia: f32,
+
+    /// Maximum angle in radians between a new segment and its parent segment.
+    max_bend_angle: Option<f32>,
+
+    /// Applies to all trees without an entry in ```root_perceptions```.
+    perception: Option<Perception<F>>,
+    root_perceptions: HashMap<NodeIdx, Perception<F>>,
+
+    obstacles: Vec<Box<dyn Obstacle<P, F> + Send + Sync>>,
+    obstacle_response: ObstacleResponse,
+    occlusion: Occlusion,
     next_iteration: u32,
     max_length: u32,
     max_branches: u32,
     use_last_n_nodes: Option<usize>,
+
+    /// Spatial index of all active nodes. ```None``` when searching
+    /// by brute-force.
+    grid: Option<Grid>,
+
+    connect_policy: ConnectPolicy,
+    venation: Venation,
+
+    /// New nodes closer than this to a node of another tree join it.
+    merge_dist: Option<SqDist>,
+    junctions: Vec<Junction>,
+
+    zero_growth: ZeroGrowth,
+    rng: XorShiftRng,
 }
 
 impl<P, F, I> SpaceColonization<P, F, I>
-    where P: FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug,
-          I: Copy + Default + Debug
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug
 {
+    /// Creates a simulation without validating the parameters. See
+    /// ```SpaceColonizationBuilder``` for a checked alternative.
     pub fn new(default_attract_dist: SqDist,
                default_connect_dist: SqDist,
                max_length: u32,
@@ -174,12 +447,364 @@ impl<P, F, I> SpaceColonization<P, F, I>
             default_connect_dist: default_connect_dist,
             max_length: max_length,
             max_branches: max_branches,
-            move_dist: move_dist,
+            growth_step: Box::new(ConstantStep(move_dist)),
+            tropism: None,
+            root_tropisms: HashMap::new(),
+            tropism_field: None,
+            inertia: 0.0,
+            max_bend_angle: None,
+            perception: None,
+            root_perceptions: HashMap::new(),
+            obstacles: Vec::new(),
+            obstacle_response: ObstacleResponse::Reject,
+            occlusion: Occlusion::Disabled,
             next_iteration: 0,
             use_last_n_nodes: None, // XXX
+            grid: Some(Grid::new(default_attract_dist.0.sqrt())),
+            connect_policy: ConnectPolicy::FirstFound,
+            venation: Venation::Open,
+            merge_dist: None,
+            junctions: Vec::new(),
+            zero_growth: ZeroGrowth::Skip,
+            rng: XorShiftRng::new_unseeded(),
+        }
+    }
+
+    /// Selects how nodes are looked up. Defaults to ```NodeSearch::Grid```.
+    pub fn set_node_search(&mut self, search: NodeSearch) {
+        self.grid = match search {
+            NodeSearch::BruteForce => None,
+            NodeSearch::Grid => {
+                let mut grid = Grid::new(self.default_attract_dist.0.sqrt());
+                for (i, node) in self.nodes.iter().enumerate() {
+                    if node.is_active(self.max_length, self.max_branches) {
+                        grid.insert(&node.position, i);
+                    }
+                }
+                Some(grid)
+            }
+        };
+    }
+
+    /// Replaces the function that computes the displacement of new
+    /// nodes. Defaults to ```ConstantStep(move_dist)```. It must be
+    /// ```Send```, so that the simulation can be moved to another thread.
+    pub fn set_growth_step<G>(&mut self, growth_step: G)
+        where G: GrowthStep<P, F, I> + Send + 'static
+    {
+        self.growth_step = Box::new(growth_step);
+    }
+
+    /// Sets the tropism of all trees that have no tropism of their own.
+    pub fn set_tropism(&mut self, tropism: Option<Tropism<F>>) {
+        self.tropism = tropism;
+    }
+
+    /// Sets the tropism of the tree rooted at ```root```, overriding
+    /// the one set by ```set_tropism```.
+    pub fn set_root_tropism(&mut self,
+                            root: NodeIdx,
+                            tropism: Option<Tropism<F>>)
+                            -> Result<(), ColonizationError> {
+        self.check_root(root)?;
+        match tropism {
+            Some(tropism) => {
+                self.root_tropisms.insert(root, tropism);
+            }
+            None => {
+                self.root_tropisms.remove(&root);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets a vector field which is sampled at the position of every
+    /// growing node. Unlike the direction of a ```Tropism```, the sampled
+    /// vector is not normalized: its length scales the pull, so that a
+    /// field can weaken smoothly towards zero. Its pull is
+    /// ```field(position) * weight``` times the length of the attractor
+    /// pull. The field must be ```Send```, so that the simulation can be moved to
+    /// another thread.
+    pub fn set_tropism_field<T>(&mut self, field: T, weight: f32)
+        where T: Fn(&P) -> F + Send + 'static
+    {
+        self.tropism_field = Some((Box::new(field), weight));
+    }
+
+    pub fn clear_tropism_field(&mut self) {
+        self.tropism_field = None;
+    }
+
+    /// Blends the tropisms into the attractor pull ```growth``` of ```node```.
+    fn apply_tropisms(&self, node: &Node<P, F, I>, growth: F) -> F {
+        let mut directed = growth;
+        let tropism = self.root_tropisms.get(&node.root).or(self.tropism.as_ref());
+        if let Some(tropism) = tropism {
+            directed = directed + tropism.pull(growth);
+        }
+        if let Some((ref field, weight)) = self.tropism_field {
+            directed = directed + field(&node.position) * (weight * growth.norm());
+        }
+        directed
+    }
+
+    /// Sets how strongly new segments keep the direction of their parent
+    /// segment. The weight is relative to the combined pull of the
+    /// attractors, like the weight of a ```Tropism```. Defaults to 0.0.
+    pub fn set_inertia(&mut self, inertia: f32) {
+        self.inertia = inertia;
+    }
+
+    /// Limits the angle (in radians) between a new segment and its parent
+    /// segment. New directions are clamped into the cone around the
+    /// parent direction. Nodes grown from a root are not limited.
+    pub fn set_max_bend_angle(&mut self, max_bend_angle: Option<f32>) {
+        self.max_bend_angle = max_bend_angle;
+    }
+
+    /// Restricts the attractors that nodes perceive to a cone. Applies
+    /// to all trees that have no perception of their own. With ```None```,
+    /// nodes perceive all attractors within the attract distance.
+    pub fn set_perception(&mut self, perception: Option<Perception<F>>) {
+        self.perception = perception;
+    }
+
+    /// Sets the perception of the tree rooted at ```root```, overriding
+    /// the one set by ```set_perception```.
+    pub fn set_root_perception(&mut self,
+                               root: NodeIdx,
+                               perception: Option<Perception<F>>)
+                               -> Result<(), ColonizationError> {
+        self.check_root(root)?;
+        match perception {
+            Some(perception) => {
+                self.root_perceptions.insert(root, perception);
+            }
+            None => {
+                self.root_perceptions.remove(&root);
+            }
+        }
+        Ok(())
+    }
+
+    /// Blends the parent direction into ```growth``` and limits the bend.
+    fn apply_inertia(&self, node: &Node<P, F, I>, growth: F) -> F {
+        if self.inertia == 0.0 && self.max_bend_angle.is_none() {
+            return growth;
+        }
+        let parent_direction = match segment_direction(&self.nodes, node) {
+            Some(direction) => direction,
+            None => return growth,
+        };
+
+        let magnitude = growth.norm();
+        let mut directed = growth + parent_direction * (self.inertia * magnitude);
+        if let (Some(max_bend_angle), Some(direction)) = (self.max_bend_angle,
+                                                          try_normalize(directed)) {
+            directed = cone::clamp_to_cone(direction, parent_direction, max_bend_angle) * magnitude;
+        }
+        directed
+    }
+
+    /// Adds an obstacle. New nodes whose segment would enter it are
+    /// handled according to the ```ObstacleResponse```. Attractors inside
+    /// of it are ignored. The obstacle must be ```Send``` and ```Sync```,
+    /// so that the simulation can be moved to another thread and searched
+    /// by ```next_parallel```.
+    pub fn add_obstacle<O>(&mut self, obstacle: O)
+        where O: Obstacle<P, F> + Send + Sync + 'static
+    {
+        self.obstacles.push(Box::new(obstacle));
+    }
+
+    /// Defaults to ```ObstacleResponse::Reject```.
+    pub fn set_obstacle_response(&mut self, obstacle_response: ObstacleResponse) {
+        self.obstacle_response = obstacle_response;
+    }
+
+    /// Selects what blocks attractors from attracting nodes. Defaults to
+    /// ```Occlusion::Disabled```.
+    pub fn set_occlusion(&mut self, occlusion: Occlusion) {
+        self.occlusion = occlusion;
+    }
+
+    /// Returns the surface normal of the first obstacle that the segment
+    /// from ```from``` to ```to``` enters.
+    fn blocking_normal(&self, from: &P, to: &P) -> Option<F> {
+        self.obstacles.iter().filter_map(|obstacle| obstacle.intersect_segment(from, to)).next()
+    }
+
+    /// Returns the displacement ```d``` of a new node grown at ```from```,
+    /// altered to avoid the obstacles, or ```None``` if it cannot be grown.
+    fn avoid_obstacles(&self, from: &P, d: F) -> Option<F> {
+        let normal = match self.blocking_normal(from, &(*from + d)) {
+            Some(normal) => normal,
+            None => return Some(d),
+        };
+
+        match self.obstacle_response {
+            ObstacleResponse::Reject => None,
+            ObstacleResponse::Deflect => {
+                // Remove the part of ```d``` pointing into the obstacle.
+                let along = d - normal * d.dot(&normal);
+                if along.sqnorm() == 0.0 {
+                    return None;
+                }
+                let deflected = along.normalize() * d.norm();
+                match self.blocking_normal(from, &(*from + deflected)) {
+                    Some(_) => None,
+                    None => Some(deflected),
+                }
+            }
+        }
+    }
+
+    /// Selects which nodes connect with an attractor. Defaults to
+    /// ```ConnectPolicy::FirstFound```.
+    pub fn set_connect_policy(&mut self, connect_policy: ConnectPolicy) {
+        self.connect_policy = connect_policy;
+    }
+
+    pub fn connect_policy(&self) -> ConnectPolicy {
+        self.connect_policy
+    }
+
+    /// Defaults to ```Venation::Open```.
+    pub fn set_venation(&mut self, venation: Venation) {
+        self.venation = venation;
+    }
+
+    pub fn venation(&self) -> Venation {
+        self.venation
+    }
+
+    /// Enables anastomosis: a new node closer than ```merge_dist``` to a
+    /// node of another tree is joined to the nearest such node, which
+    /// becomes its extra parent. Two trees are joined at most once.
+    /// Disabled (```None```) by default.
+    pub fn set_merge_dist(&mut self, merge_dist: Option<SqDist>) {
+        self.merge_dist = merge_dist;
+    }
+
+    pub fn merge_dist(&self) -> Option<SqDist> {
+        self.merge_dist
+    }
+
+    /// The junctions between trees, in the order they were created.
+    pub fn junctions(&self) -> &[Junction] {
+        &self.junctions
+    }
+
+    /// Joins the node ```idx``` to the nearest node of another tree within
+    /// the merge distance, looked up in ```all_nodes```, unless the two
+    /// trees are already joined. Ties go to the lower index.
+    fn anastomose(&mut self, idx: usize, all_nodes: &Grid) -> bool {
+        let merge_dist = match self.merge_dist {
+            Some(merge_dist) => merge_dist,
+            None => return false,
+        };
+        let (position, root) = (self.nodes[idx].position, self.nodes[idx].root);
+
+        let mut nearest: Option<(SqDist, usize)> = None;
+        {
+            let nodes = &self.nodes;
+            let junctions = &self.junctions;
+            all_nodes.visit_near(&position, merge_dist.0.sqrt(), &mut |j| {
+                let other_root = nodes[j].root;
+                if other_root == root || junctions.iter().any(|jn| jn.joins(root, other_root)) {
+                    return;
+                }
+                let dist = SqDist(nodes[j].position.sqdist(&position));
+                let closer = match nearest {
+                    Some((d, k)) => dist < d || (dist == d && j < k),
+                    None => true,
+                };
+                if dist < merge_dist && closer {
+                    nearest = Some((dist, j));
+                }
+            });
+        }
+
+        match nearest {
+            Some((_, other)) => {
+                self.join_nodes(idx, other);
+                self.junctions.push(Junction {
+                    node: NodeIdx(idx as u32),
+                    other: NodeIdx(other as u32),
+                    root: root,
+                    other_root: self.nodes[other].root,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Selects what nodes do whose growth direction vanishes. Defaults
+    /// to ```ZeroGrowth::Skip```.
+    pub fn set_zero_growth(&mut self, zero_growth: ZeroGrowth) {
+        self.zero_growth = zero_growth;
+    }
+
+    pub fn zero_growth(&self) -> ZeroGrowth {
+        self.zero_growth
+    }
+
+    /// Reseeds the random number generator used by
+    /// ```ZeroGrowth::RandomDirection```. Runs with the same seed grow the
+    /// same nodes. An all-zero seed, which the generator cannot use,
+    /// restores the default seed.
+    pub fn reseed(&mut self, seed: [u32; 4]) {
+        if seed == [0; 4] {
+            self.rng = XorShiftRng::new_unseeded();
+        } else {
+            self.rng.reseed(seed);
+        }
+    }
+
+    /// The growth direction replacing the vanished one of the node with
+    /// index ```i```, or ```None``` if the node is not grown. Like any
+    /// other growth, it is subject to inertia and the maximum bend angle.
+    fn zero_growth_direction(&mut self, i: usize) -> Option<F> {
+        let direction = match self.zero_growth {
+            ZeroGrowth::Skip => None,
+            ZeroGrowth::RandomDirection => Some(sampling::sample_direction(&mut self.rng)),
+            ZeroGrowth::ParentDirection => segment_direction(&self.nodes, &self.nodes[i]),
+        };
+        direction.map(|direction| self.apply_inertia(&self.nodes[i], direction))
+                 .and_then(try_normalize)
+    }
+
+    /// Returns an error naming the first node whose position is not
+    /// finite.
+    pub fn check_positions(&self) -> Result<(), ColonizationError> {
+        match self.nodes.iter().position(|node| !is_finite(&node.position)) {
+            Some(i) => Err(ColonizationError::NonFinitePosition(NodeIdx(i as u32))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn node_search(&self) -> NodeSearch {
+        match self.grid {
+            Some(_) => NodeSearch::Grid,
+            None => NodeSearch::BruteForce,
         }
     }
 
+    /// Moves every node and attractor to ```transform(position)```.
+    pub fn transform_positions<T>(&mut self, transform: T)
+        where T: Fn(&P) -> P
+    {
+        for node in self.nodes.iter_mut() {
+            node.position = transform(&node.position);
+        }
+        for attractor in self.attractors.iter_mut() {
+            attractor.position = transform(&attractor.position);
+        }
+        let search = self.node_search();
+        self.set_node_search(search);
+    }
+
     pub fn add_attractor(&mut self, attractor: Attractor<P, I>) {
         self.attractors.push(attractor);
     }
@@ -198,20 +823,26 @@ impl<P, F, I> SpaceColonization<P, F, I>
         });
     }
 
-    pub fn add_root_node(&mut self, position: P) -> NodeIdx {
+    pub fn add_root_node(&mut self, position: P) -> Result<NodeIdx, ColonizationError> {
         self.add_root_node_with_information(position, None)
     }
 
-    /// Returns the root node's index.
+    /// Returns the root node's index, or an error if ```position``` is
+    /// not finite.
     pub fn add_root_node_with_information(&mut self,
                                           position: P,
                                           information: Option<I>)
-                                          -> NodeIdx {
+                                          -> Result<NodeIdx, ColonizationError> {
         // A root node has it's own index as parent and root.
         let len = self.nodes.len();
         let root_idx = NodeIdx(len as u32);
-        self.nodes.push(Node {
+        if !is_finite(&position) {
+            return Err(ColonizationError::NonFinitePosition(root_idx));
+        }
+        self.push_node(Node {
             parent: root_idx,
+            extra_parents: Vec::new(),
+            children: Vec::new(),
             root: root_idx,
             length: 0,
             branches: 0,
@@ -220,26 +851,134 @@ impl<P, F, I> SpaceColonization<P, F, I>
             growth_count: 0,
             assigned_information: information,
         });
-        root_idx
+        Ok(root_idx)
     }
 
-    fn get_node(&self, node_idx: NodeIdx) -> Option<&Node<P, F, I>> {
+    fn push_node(&mut self, node: Node<P, F, I>) {
+        let idx = self.nodes.len();
+        if node.is_active(self.max_length, self.max_branches) {
+            if let Some(ref mut grid) = self.grid {
+                grid.insert(&node.position, idx);
+            }
+        }
+        if let Some(parent) = node.parent() {
+            self.nodes[parent.0 as usize].children.push(NodeIdx(idx as u32));
+        }
+        self.nodes.push(node);
+    }
+
+    /// The node at ```node_idx```, or ```None``` if there is none.
+    pub fn get_node(&self, node_idx: NodeIdx) -> Option<&Node<P, F, I>> {
         self.nodes.get(node_idx.0 as usize)
     }
 
-    fn get_node_mut(&mut self, node_idx: NodeIdx) -> Option<&mut Node<P, F, I>> {
-        self.nodes.get_mut(node_idx.0 as usize)
+    /// Like ```get_node```, but reports a missing node as error.
+    pub fn node(&self, node_idx: NodeIdx) -> Result<&Node<P, F, I>, ColonizationError> {
+        self.get_node(node_idx).ok_or(ColonizationError::InvalidNodeIdx(node_idx))
+    }
+
+    fn check_root(&self, node_idx: NodeIdx) -> Result<(), ColonizationError> {
+        if self.node(node_idx)?.is_root() {
+            Ok(())
+        } else {
+            Err(ColonizationError::NotARoot(node_idx))
+        }
+    }
+
+    /// Number of nodes of all trees.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The direct parent of ```node_idx```, or ```None``` for root nodes.
+    pub fn parent(&self, node_idx: NodeIdx) -> Result<Option<NodeIdx>, ColonizationError> {
+        Ok(self.node(node_idx)?.parent())
+    }
+
+    /// The nodes that have ```node_idx``` as direct parent.
+    pub fn children(&self, node_idx: NodeIdx) -> Result<&[NodeIdx], ColonizationError> {
+        Ok(self.node(node_idx)?.children())
+    }
+
+    /// Iterates depth-first in pre-order over the subtree rooted at
+    /// ```node_idx```, starting with ```node_idx``` itself. Extra parents
+    /// are ignored, so every node is visited once.
+    pub fn subtree_dfs(&self, node_idx: NodeIdx) -> Result<DepthFirst<P, F, I>, ColonizationError> {
+        self.node(node_idx)?;
+        Ok(DepthFirst::new(&self.nodes, node_idx))
+    }
+
+    /// Iterates breadth-first over the subtree rooted at ```node_idx```,
+    /// starting with ```node_idx``` itself. Extra parents are ignored.
+    pub fn subtree_bfs(&self, node_idx: NodeIdx) -> Result<BreadthFirst<P, F, I>, ColonizationError> {
+        self.node(node_idx)?;
+        Ok(BreadthFirst::new(&self.nodes, node_idx))
+    }
+
+    /// Iterates from ```node_idx``` along the direct parents up to its
+    /// root, both included.
+    pub fn path_to_root(&self, node_idx: NodeIdx) -> Result<PathToRoot<P, F, I>, ColonizationError> {
+        self.node(node_idx)?;
+        Ok(PathToRoot::new(&self.nodes, node_idx))
+    }
+
+    /// The deepest common node on the paths of ```a``` and ```b``` to
+    /// the root, or ```None``` if they belong to different trees.
+    pub fn lowest_common_ancestor(&self,
+                                  a: NodeIdx,
+                                  b: NodeIdx)
+                                  -> Result<Option<NodeIdx>, ColonizationError> {
+        self.node(a)?;
+        self.node(b)?;
+        Ok(topology::lowest_common_ancestor(&self.nodes, a, b))
+    }
+
+    /// Counts another branch of ```parent``` and removes it from the grid
+    /// if that deactivates it.
+    fn add_branch(&mut self, parent: NodeIdx) {
+        let max_length = self.max_length;
+        let max_branches = self.max_branches;
+        let (parent_position, parent_deactivated) = {
+            let parent_node = &mut self.nodes[parent.0 as usize];
+            let was_active = parent_node.is_active(max_length, max_branches);
+            parent_node.branches += 1;
+            (parent_node.position,
+             was_active && !parent_node.is_active(max_length, max_branches))
+        };
+
+        if parent_deactivated {
+            if let Some(ref mut grid) = self.grid {
+                grid.remove(&parent_position, parent.0 as usize);
+            }
+        }
+    }
+
+    /// Grows a node at ```position``` from ```parent``` by hand. Returns
+    /// the new node's index, or an error if ```parent``` does not exist or
+    /// ```position``` is not finite.
+    pub fn add_child_node(&mut self,
+                          position: P,
+                          parent: NodeIdx)
+                          -> Result<NodeIdx, ColonizationError> {
+        self.node(parent)?;
+        if !is_finite(&position) {
+            return Err(ColonizationError::NonFinitePosition(NodeIdx(self.nodes.len() as u32)));
+        }
+        self.add_leaf_node(position, parent);
+        Ok(NodeIdx(self.nodes.len() as u32 - 1))
     }
 
     fn add_leaf_node(&mut self, position: P, parent: NodeIdx) {
+        self.add_branch(parent);
         let (root, length) = {
-            let parent_node = self.get_node_mut(parent).unwrap();
-            parent_node.branches += 1;
+            let parent_node = &self.nodes[parent.0 as usize];
             (parent_node.root, parent_node.length + 1)
         };
 
-        self.nodes.push(Node {
+        self.push_node(Node {
             parent: parent,
+            extra_parents: Vec::new(),
+            children: Vec::new(),
             root: root,
             length: length,
             branches: 0,
@@ -250,10 +989,101 @@ impl<P, F, I> SpaceColonization<P, F, I>
         });
     }
 
+    /// Makes ```child``` a child of ```parent``` as well, unless the two
+    /// are already joined. Returns true if they were not.
+    fn join_nodes(&mut self, child: usize, parent: usize) -> bool {
+        let joined = {
+            let (c, p) = (&self.nodes[child], &self.nodes[parent]);
+            c.parent.0 as usize == parent || p.parent.0 as usize == child ||
+            c.extra_parents.contains(&NodeIdx(parent as u32)) ||
+            p.extra_parents.contains(&NodeIdx(child as u32))
+        };
+        if joined || child == parent {
+            return false;
+        }
+        self.add_branch(NodeIdx(parent as u32));
+        self.nodes[child].extra_parents.push(NodeIdx(parent as u32));
+        true
+    }
+
+    /// Propagates the information of every node along the parent links
+    /// towards the root. Children are visited before their parent and
+    /// their information is combined into the parent's by
+    /// ```combine(parent, child)```. Children are visited from the last
+    /// added to the first, so a parent without information starts with a
+    /// copy of the last added child's that holds any. Extra parents are
+    /// ignored.
+    pub fn propagate_up<C>(&mut self, mut combine: C)
+        where C: FnMut(&mut I, &I)
+    {
+        // The index of a parent is always lower than that of its children.
+        for i in (0..self.nodes.len()).rev() {
+            let (head, tail) = self.nodes.split_at_mut(i);
+            let child = &tail[0];
+            if child.is_root() {
+                continue;
+            }
+            if let Some(ref information) = child.assigned_information {
+                let parent = &mut head[child.parent.0 as usize];
+                combine_information(&mut parent.assigned_information, information, &mut combine);
+            }
+        }
+    }
+
+    /// Propagates the information of every node along the parent links
+    /// into its subtree. Parents are visited before their children and
+    /// their information is combined into the child's by
+    /// ```combine(child, parent)```. Extra parents are ignored.
+    pub fn propagate_down<C>(&mut self, mut combine: C)
+        where C: FnMut(&mut I, &I)
+    {
+        for i in 0..self.nodes.len() {
+            let (head, tail) = self.nodes.split_at_mut(i);
+            let child = &mut tail[0];
+            if child.is_root() {
+                continue;
+            }
+            if let Some(ref information) = head[child.parent.0 as usize].assigned_information {
+                combine_information(&mut child.assigned_information, information, &mut combine);
+            }
+        }
+    }
+
+    /// Iterates over all attractors.
+    pub fn attractors(&self) -> slice::Iter<Attractor<P, I>> {
+        self.attractors.iter()
+    }
+
+    /// Iterates over the attractors that are active in ```iteration```.
+    pub fn active_attractors(&self, iteration: u32) -> ActiveAttractors<P, I> {
+        ActiveAttractors::new(&self.attractors, iteration)
+    }
+
+    /// Iterates over all nodes in the order they were added.
+    pub fn nodes(&self) -> Nodes<P, F, I> {
+        Nodes::new(&self.nodes)
+    }
+
+    /// Iterates over the root nodes.
+    pub fn roots(&self) -> FilterNodes<P, F, I> {
+        FilterNodes::new(&self.nodes, Node::is_root)
+    }
+
+    /// Iterates over the nodes without branches.
+    pub fn leaves(&self) -> FilterNodes<P, F, I> {
+        FilterNodes::new(&self.nodes, Node::is_leaf)
+    }
+
+    /// Iterates over the segments from every node to its parents,
+    /// including the extra parents.
+    pub fn segments(&self) -> Segments<P, F, I> {
+        Segments::new(&self.nodes)
+    }
+
     pub fn visit_attractor_points<V>(&self, visitor: &mut V)
         where V: FnMut(&P)
     {
-        for attractor in self.attractors.iter() {
+        for attractor in self.attractors() {
             visitor(&attractor.position)
         }
     }
@@ -261,20 +1091,18 @@ impl<P, F, I> SpaceColonization<P, F, I>
     pub fn visit_attractors<V>(&self, visitor: &mut V)
         where V: FnMut(&Attractor<P, I>)
     {
-        for attractor in self.attractors.iter() {
+        for attractor in self.attractors() {
             visitor(attractor)
         }
     }
 
-
+    /// Calls the visitor with the positions of every node and its parents,
+    /// including the extra parents.
     pub fn visit_node_segments<V>(&self, visitor: &mut V)
         where V: FnMut(&P, &P)
     {
-        for node in self.nodes.iter() {
-            if !node.is_root() {
-                visitor(&node.position,
-                        &self.get_node(node.parent).unwrap().position);
-            }
+        for segment in self.segments() {
+            visitor(segment.position, segment.parent_position);
         }
     }
 
@@ -284,136 +1112,544 @@ impl<P, F, I> SpaceColonization<P, F, I>
     pub fn visit_nodes_with_info_and_root<V>(&self, visitor: &mut V)
         where V: FnMut(&Node<P, F, I>, &Node<P, F, I>)
     {
-        for node in self.nodes.iter() {
+        for node in self.nodes() {
             if node.assigned_information.is_some() && !node.is_root() {
-                visitor(node, &self.get_node(node.root).unwrap());
+                visitor(node.node, &self.nodes[node.root.0 as usize]);
             }
         }
     }
 
+    pub fn visit_nodes<V>(&self, visitor: &mut V)
+        where V: FnMut(&Node<P, F, I>)
+    {
+        for node in self.nodes() {
+            visitor(node.node);
+        }
+    }
+
     pub fn visit_root_nodes<V>(&self, visitor: &mut V)
         where V: FnMut(&Node<P, F, I>)
     {
-        for node in self.nodes.iter() {
-            if node.is_root() {
-                visitor(node);
-            }
+        for node in self.roots() {
+            visitor(node.node);
         }
     }
 }
 
-impl<P, F, I> Iterator for SpaceColonization<P, F, I>
+/// Combines ```information``` into ```target```, or copies it if
+/// ```target``` holds nothing yet.
+fn combine_information<I, C>(target: &mut Option<I>, information: &I, combine: &mut C)
+    where I: Clone,
+          C: FnMut(&mut I, &I)
+{
+    match *target {
+        Some(ref mut target) => combine(target, information),
+        None => *target = Some(information.clone()),
+    }
+}
+
+/// The normalized direction of the segment leading to ```node```.
+/// ```None``` for root nodes and segments of zero length.
+fn segment_direction<P, F, I>(nodes: &[Node<P, F, I>], node: &Node<P, F, I>) -> Option<F>
     where P: FloatPnt<f32, F> + Debug,
-          F: FloatVec<f32> + Zero + Copy + Debug,
-          I: Copy + Default + Debug
+          F: FloatVec<f32> + Copy + Debug,
+          I: Clone + Debug
 {
-    type Item = usize;
+    if node.is_root() {
+        None
+    } else {
+        let parent = &nodes[node.parent.0 as usize];
+        try_normalize(node.position - parent.position)
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
+/// Normalizes ```v```, or returns ```None``` if it has no direction
+/// because it is zero (or so short that its square norm underflows) or
+/// not finite. There is no tolerance, so that tiny vectors at small
+/// scales keep their direction.
+fn try_normalize<F>(v: F) -> Option<F>
+    where F: FloatVec<f32> + Copy
+{
+    let sqnorm = v.sqnorm();
+    if sqnorm > 0.0 && sqnorm.is_finite() {
+        Some(v * (1.0 / sqnorm.sqrt()))
+    } else {
+        None
+    }
+}
+
+fn is_finite<P: Coords>(p: &P) -> bool {
+    (0..P::dimensions()).all(|axis| p.coord(axis).is_finite())
+}
+
+/// The state shared by the node searches of all attractors within
+/// one iteration. It is never modified during the search, so the
+/// attractors can be processed in any order, or in parallel.
+struct Search<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    nodes: &'a [Node<P, F, I>],
+    grid: Option<&'a Grid>,
+    max_length: u32,
+    max_branches: u32,
+    start_index: usize,
+    current_iteration: u32,
+    connect_policy: ConnectPolicy,
+    venation: Venation,
+    perception: Option<&'a Perception<F>>,
+    root_perceptions: &'a HashMap<NodeIdx, Perception<F>>,
+    obstacles: &'a [Box<dyn Obstacle<P, F> + Send + Sync>],
+    occlusion: Occlusion,
+
+    /// All non-root nodes, keyed by the midpoint of their segment.
+    /// Only built for ```Occlusion::ObstaclesAndBranches```.
+    segments: Option<&'a Grid>,
+    max_segment_length: f32,
+}
+
+impl<'a, P, F, I> Search<'a, P, F, I>
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug
+{
+    /// Returns the index and square distance of every node with index
+    /// ```start_index``` or higher that is able to interact with the
+    /// attractor ```ap```, in index order.
+    fn candidates(&self, ap: &Attractor<P, I>) -> Vec<(usize, SqDist)> {
+        let max_dist = if ap.connect_dist > ap.attract_dist {
+            ap.connect_dist
+        } else {
+            ap.attract_dist
+        };
+        let mut candidates = Vec::new();
+
+        match self.grid {
+            None => {
+                for (i, node) in self.nodes.iter().enumerate().skip(self.start_index) {
+                    if !node.is_active(self.max_length, self.max_branches) ||
+                       !ap.accepts_root(node.root) {
+                        continue;
+                    }
+                    let dist = SqDist(node.position.sqdist(&ap.position));
+                    if dist < max_dist {
+                        candidates.push((i, dist));
+                    }
+                }
+            }
+            Some(grid) => {
+                // The grid only contains active nodes.
+                let nodes = self.nodes;
+                let start_index = self.start_index;
+                grid.visit_near(&ap.position, max_dist.0.sqrt(), &mut |i| {
+                    if i < start_index || !ap.accepts_root(nodes[i].root) {
+                        return;
+                    }
+                    let dist = SqDist(nodes[i].position.sqdist(&ap.position));
+                    if dist < max_dist {
+                        candidates.push((i, dist));
+                    }
+                });
+                // The grid visits nodes in no particular order, so emulate
+                // the index order of the brute-force search.
+                candidates.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+        }
+
+        candidates
+    }
+
+    /// Returns true if the node with index ```i``` perceives ```ap```.
+    fn perceives(&self, i: usize, ap: &Attractor<P, I>) -> bool {
+        let node = &self.nodes[i];
+        let perception = match self.root_perceptions.get(&node.root).or(self.perception) {
+            Some(perception) => perception,
+            None => return true,
+        };
+        let axis = match segment_direction(self.nodes, node) {
+            Some(direction) => direction,
+            None => {
+                match perception.root_direction {
+                    Some(direction) => direction.normalize(),
+                    None => return true,
+                }
+            }
+        };
+        match try_normalize(ap.position - node.position) {
+            Some(direction) => cone::within_cone(direction, axis, perception.half_angle),
+            // An attractor on the node lies in every cone.
+            None => true,
+        }
+    }
+
+    /// Returns true if nothing blocks the line of sight between the node
+    /// with index ```i``` and ```ap```.
+    fn sees(&self, i: usize, ap: &Attractor<P, I>) -> bool {
+        let radius = match self.occlusion {
+            Occlusion::Disabled => return true,
+            Occlusion::Obstacles => None,
+            Occlusion::ObstaclesAndBranches { radius } => Some(radius),
+        };
+
+        let node = &self.nodes[i];
+        if self.obstacles
+               .iter()
+               .any(|obstacle| obstacle.intersect_segment(&node.position, &ap.position).is_some()) {
+            return false;
+        }
+
+        match (radius, self.segments) {
+            (Some(radius), Some(segments)) => {
+                let sight = ap.position - node.position;
+                let middle = node.position + sight * 0.5;
+                let reach = 0.5 * (sight.norm() + self.max_segment_length) + radius;
+                let nodes = self.nodes;
+                let mut visible = true;
+                segments.visit_near(&middle, reach, &mut |j| {
+                    let parent = nodes[j].parent.0 as usize;
+                    if !visible || j == i || parent == i {
+                        return;
+                    }
+                    let dist = segment::segment_sqdist(&node.position,
+                                                       &ap.position,
+                                                       &nodes[parent].position,
+                                                       &nodes[j].position);
+                    if dist < radius * radius {
+                        visible = false;
+                    }
+                });
+                visible
+            }
+            _ => true,
+        }
+    }
+
+    /// Finds the nodes that the attractor ```ap``` connects with or
+    /// attracts. Returns ```None``` if the attractor is not active in the
+    /// current iteration or lies inside an obstacle.
+    fn influence(&self, ap: &Attractor<P, I>) -> Option<Influence> {
+        if !ap.is_active_in(self.current_iteration) ||
+           self.obstacles.iter().any(|obstacle| obstacle.contains(&ap.position)) {
+            return None;
+        }
+
+        match self.venation {
+            Venation::Open => Some(self.open_influence(ap)),
+            Venation::Closed => Some(self.closed_influence(ap)),
+        }
+    }
+
+    /// The nodes that connect are chosen by the ```ConnectPolicy```. The
+    /// attracted node is the closest one within the attract distance that
+    /// perceives and sees the attractor, ties going to the lower index.
+    /// It is only set if no node connects.
+    fn open_influence(&self, ap: &Attractor<P, I>) -> Influence {
+        let mut connect: Vec<(SqDist, usize)> = Vec::new();
+        let mut nearest = None;
+        let mut nearest_distance = ap.attract_dist;
+
+        for (i, dist) in self.candidates(ap) {
+            if dist < ap.connect_dist {
+                // This node is within the connect radius of the attractor.
+                match self.connect_policy {
+                    ConnectPolicy::FirstFound => {
+                        if connect.is_empty() {
+                            connect.push((dist, i));
+                        }
+                    }
+                    ConnectPolicy::Nearest => {
+                        if connect.is_empty() || dist < connect[0].0 {
+                            connect.clear();
+                            connect.push((dist, i));
+                        }
+                    }
+                    ConnectPolicy::AllWithinRadius => {
+                        connect.push((dist, i));
+                    }
+                }
+            } else if dist < nearest_distance && self.perceives(i, ap) && self.sees(i, ap) {
+                // ```node``` is within the influence of the attraction point,
+                // and it's closer than the currently closest node.
+                nearest_distance = dist;
+                nearest = Some(i);
+            }
+        }
+
+        // Nearest first. The sort is stable, so ties keep index order.
+        connect.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        Influence {
+            attracted: match nearest {
+                Some(i) if connect.is_empty() => vec![i],
+                _ => Vec::new(),
+            },
+            connect: connect.into_iter().map(|(_, i)| i).collect(),
+        }
+    }
+
+    /// Determines the relative neighborhood of ```ap``` among the nodes
+    /// that connect with it, or perceive and see it within the attract
+    /// distance. The nodes connect once all of the neighborhood has
+    /// reached the connect distance. Until then, the others are attracted.
+    fn closed_influence(&self, ap: &Attractor<P, I>) -> Influence {
+        let eligible: Vec<(usize, SqDist)> = self.candidates(ap)
+                                                 .into_iter()
+                                                 .filter(|&(i, dist)| {
+                                                     dist < ap.connect_dist ||
+                                                     (dist < ap.attract_dist &&
+                                                      self.perceives(i, ap) &&
+                                                      self.sees(i, ap))
+                                                 })
+                                                 .collect();
+
+        let mut reached: Vec<(SqDist, usize)> = Vec::new();
+        let mut attracted = Vec::new();
+        for &(i, dist) in eligible.iter() {
+            let position = &self.nodes[i].position;
+            // Ties go to the lower index, so that coincident nodes do not
+            // all grow.
+            let shadowed = eligible.iter().any(|&(j, other_dist)| {
+                (other_dist < dist || (other_dist == dist && j < i)) &&
+                SqDist(self.nodes[j].position.sqdist(position)) < dist
+            });
+            if shadowed {
+                continue;
+            }
+            if dist < ap.connect_dist {
+                reached.push((dist, i));
+            } else {
+                attracted.push(i);
+            }
+        }
+
+        if !attracted.is_empty() {
+            return Influence {
+                connect: Vec::new(),
+                attracted: attracted,
+            };
+        }
+
+        reached.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        Influence {
+            connect: reached.into_iter().map(|(_, i)| i).collect(),
+            attracted: Vec::new(),
+        }
+    }
+}
+
+impl<P, F, I> SpaceColonization<P, F, I>
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug
+{
+    /// Searches the influenced nodes of all attractors with
+    /// ```search_all```. The result is indexed like ```self.attractors```.
+    fn find_influences<S>(&self,
+                          current_iteration: u32,
+                          start_index: usize,
+                          search_all: S)
+                          -> Vec<Option<Influence>>
+        where S: FnOnce(&Search<P, F, I>, &[Attractor<P, I>]) -> Vec<Option<Influence>>
+    {
+        let mut max_segment_length = 0.0;
+        let segments = match self.occlusion {
+            Occlusion::ObstaclesAndBranches { .. } => {
+                let mut grid = Grid::new(self.default_attract_dist.0.sqrt());
+                for (i, node) in self.nodes.iter().enumerate() {
+                    if !node.is_root() {
+                        let parent = &self.nodes[node.parent.0 as usize];
+                        let segment = node.position - parent.position;
+                        grid.insert(&(parent.position + segment * 0.5), i);
+                        max_segment_length = segment.norm().max(max_segment_length);
+                    }
+                }
+                Some(grid)
+            }
+            _ => None,
+        };
+
+        let search = Search {
+            nodes: &self.nodes,
+            grid: self.grid.as_ref(),
+            max_length: self.max_length,
+            max_branches: self.max_branches,
+            start_index: start_index,
+            current_iteration: current_iteration,
+            connect_policy: self.connect_policy,
+            venation: self.venation,
+            perception: self.perception.as_ref(),
+            root_perceptions: &self.root_perceptions,
+            obstacles: &self.obstacles,
+            occlusion: self.occlusion,
+            segments: segments.as_ref(),
+            max_segment_length: max_segment_length,
+        };
+
+        search_all(&search, &self.attractors)
+    }
+
+    /// Performs one iteration like ```next```, but searches the nodes of
+    /// all attractors on multiple threads. The result is identical to
+    /// that of ```next```.
+    #[cfg(feature = "parallel")]
+    pub fn next_parallel(&mut self) -> Option<IterationReport>
+        where P: Sync,
+              F: Sync,
+              I: Sync
+    {
+        self.iterate(|search, attractors| {
+            attractors.par_iter().map(|ap| search.influence(ap)).collect()
+        })
+    }
+
+    /// Performs one iteration, searching the nodes of all attractors
+    /// with ```search_all```.
+    fn iterate<S>(&mut self, search_all: S) -> Option<IterationReport>
+        where S: FnOnce(&Search<P, F, I>, &[Attractor<P, I>]) -> Vec<Option<Influence>>
+    {
         let max_length = self.max_length;
         let max_branches = self.max_branches;
+        if self.attractors.is_empty() ||
+           !self.nodes.iter().any(|node| node.is_active(max_length, max_branches)) {
+            return None;
+        }
 
         let current_iteration = self.next_iteration;
         self.next_iteration += 1;
         let num_nodes = self.nodes.len();
         let use_last_nodes: usize = cmp::min(num_nodes, self.use_last_n_nodes.unwrap_or(num_nodes));
         let start_index = num_nodes - use_last_nodes;
+        let mut report = IterationReport {
+            iteration: current_iteration,
+            new_nodes: 0,
+            blocked_nodes: 0,
+            zero_growth_nodes: 0,
+            killed_attractors: 0,
+            disabled_attractors: 0,
+            information_transmissions: 0,
+            joined_nodes: 0,
+            new_junctions: 0,
+            active_nodes: 0,
+            active_attractors: 0,
+        };
 
         // for each attraction_point, find the nearest node that it influences
+        let mut influences = self.find_influences(current_iteration, start_index, search_all);
+
+        // then apply them in attractor order, which makes the result
+        // independent of how the search was performed.
         let mut ap_idx = 0;
         'outer: while ap_idx < self.attractors.len() {
-            let ap = {
-                let ap_ref = &self.attractors[ap_idx];
-
-                if !ap_ref.is_active_in(current_iteration) {
+            let influence = match influences[ap_idx].take() {
+                Some(influence) => influence,
+                None => {
                     // is attractor is not active in the current iteration goto next.
                     ap_idx += 1;
                     continue;
                 }
-
-                *ap_ref
+            };
+            let (position, strength, connect_action) = {
+                let ap = &self.attractors[ap_idx];
+                (ap.position, ap.strength, ap.connect_action)
             };
 
-            let nodes = &mut self.nodes[start_index..];
-
-            // find the node nearest to the `ap` attraction point
-            let mut nearest_node: Option<&mut Node<_, _, _>> = None;
-            let mut nearest_distance = ap.attract_dist;
-            let mut connect_node: Option<&mut Node<_, _, _>> = None;
-            for node in nodes.iter_mut() {
-                if !node.is_active(max_length, max_branches) {
-                    // The node has become inactive
-                    continue;
-                }
-
-                match ap.not_for_root {
-                    Some(deny_root) if deny_root == node.root => {
-                        // The attractor is not for this tree node.
-                        continue;
-                    }
-                    _ => {}
+            if let Some(&i) = influence.connect.first() {
+                for &j in influence.connect.iter() {
+                    self.nodes[j].transmit_information(&self.attractors[ap_idx].information);
+                    report.information_transmissions += 1;
                 }
-
-                match ap.not_for_connecting_root {
-                    Some(deny_root) if deny_root == node.root => {
-                        // The attractor is not for this tree node.
-                        continue;
+                if self.venation == Venation::Closed {
+                    for &j in influence.connect[1..].iter() {
+                        if self.join_nodes(i, j) {
+                            report.joined_nodes += 1;
+                        }
                     }
-                    _ => {}
-                }
-
-                let dist = SqDist(node.position.sqdist(&ap.position));
-
-                if dist < ap.connect_dist {
-                    // This node is within the connect radius of a node.
-                    // XXX: There might be a closer node, but we use
-                    // the first we find.
-                    connect_node = Some(node);
-                    // outside the node loop, we perform some action
-                    break;
-                } else if dist < nearest_distance {
-                    // ```node``` is within the influence of the attraction point,
-                    // and it's closer than the currently closest node.
-                    nearest_distance = dist;
-                    nearest_node = Some(node);
                 }
-            }
-
-            if let Some(node) = connect_node {
-                node.transmit_information(ap.information);
-                match ap.connect_action {
+                let node = &self.nodes[i];
+                match connect_action {
                     ConnectAction::KillAttractor => {
                         // remove attraction point
                         self.attractors.swap_remove(ap_idx);
+                        influences.swap_remove(ap_idx);
+                        report.killed_attractors += 1;
                         // and continue with "next" (without increasing ap_idx)
                         continue 'outer;
                     }
                     ConnectAction::DisableFor {iterations} => {
                         self.attractors[ap_idx].disable_until(current_iteration + iterations);
+                        report.disabled_attractors += 1;
                     }
                     ConnectAction::DisableForConnectingRoot => {
-                        self.attractors[ap_idx].not_for_connecting_root = Some(node.root)
+                        self.attractors[ap_idx].not_for_connecting_root = Some(node.root);
+                        report.disabled_attractors += 1;
+                    }
+                }
+            } else {
+                for &i in influence.attracted.iter() {
+                    let node = &mut self.nodes[i];
+                    // update the force with the normalized vector towards the attraction point.
+                    // An attractor on the node pulls in no direction.
+                    if let Some(direction) = try_normalize(position - node.position) {
+                        node.growth = node.growth + direction * strength;
                     }
+                    node.growth_count += 1;
                 }
-            } else if let Some(node) = nearest_node {
-                // update the force with the normalized vector towards the attraction point
-                let v = (ap.position - node.position).normalize() * ap.strength;
-                node.growth = node.growth + v;
-                node.growth_count += 1;
             }
 
             // go to next attractor point
             ap_idx += 1;
         }
 
+        // All nodes, including the inactive ones, that new nodes may merge with.
+        let mut all_nodes = self.merge_dist.map(|merge_dist| {
+            let mut grid = Grid::new(merge_dist.0.sqrt());
+            for (i, node) in self.nodes.iter().enumerate() {
+                grid.insert(&node.position, i);
+            }
+            grid
+        });
+
         // now create new nodes
         for i in start_index..num_nodes {
             let growth_count = self.nodes[i].growth_count;
             if growth_count > 0 {
-                let growth_factor = 1.0; //((growth_count + 1) as f32).ln();
-                let d = self.nodes[i].growth.normalize() * self.move_dist * growth_factor;
-                let new_position = self.nodes[i].position + d;
-                self.add_leaf_node(new_position, NodeIdx(i as u32));
+                let growth = {
+                    let node = &self.nodes[i];
+                    let growth = self.apply_tropisms(node, node.growth);
+                    self.apply_inertia(node, growth)
+                };
+                let growth = match try_normalize(growth) {
+                    Some(_) => Some(growth),
+                    None => {
+                        report.zero_growth_nodes += 1;
+                        self.zero_growth_direction(i)
+                    }
+                };
+                let d = growth.and_then(|growth| {
+                    let node = &self.nodes[i];
+                    let d = self.growth_step.step(node, growth, growth_count);
+                    self.avoid_obstacles(&node.position, d)
+                });
+                match (growth, d) {
+                    (None, _) => {}
+                    (Some(_), Some(d)) => {
+                        let new_position = self.nodes[i].position + d;
+                        let new_idx = self.nodes.len();
+                        debug_assert!(is_finite(&new_position),
+                                      "{}: {:?}",
+                                      ColonizationError::NonFinitePosition(NodeIdx(new_idx as u32)),
+                                      new_position);
+                        self.add_leaf_node(new_position, NodeIdx(i as u32));
+                        if let Some(ref mut grid) = all_nodes {
+                            if self.anastomose(new_idx, grid) {
+                                report.new_junctions += 1;
+                            }
+                            grid.insert(&new_position, new_idx);
+                        }
+                    }
+                    (Some(_), None) => report.blocked_nodes += 1,
+                }
 
                 // and reset growth attraction forces
                 self.nodes[i].growth = Zero::zero();
@@ -421,8 +1657,325 @@ impl<P, F, I> Iterator for SpaceColonization<P, F, I>
             }
         }
 
+        report.new_nodes = self.nodes.len() - num_nodes;
+        report.active_nodes = self.nodes
+                                  .iter()
+                                  .filter(|node| node.is_active(max_length, max_branches))
+                                  .count();
+        report.active_attractors = self.attractors
+                                       .iter()
+                                       .filter(|ap| ap.is_active_in(self.next_iteration))
+                                       .count();
+
         // Note that nodes can oscillate, between two attraction points, so
         // it's better to stop after a certain number of iterations
-        return Some(self.nodes.len() - num_nodes);
+        return Some(report);
+    }
+}
+
+impl<P, F, I> Iterator for SpaceColonization<P, F, I>
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug
+{
+    type Item = IterationReport;
+
+    /// Performs one iteration. Returns ```None``` once nothing can
+    /// happen anymore, that is when no attractor is left or no node
+    /// can grow.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterate(|search, attractors| attractors.iter().map(|ap| search.influence(ap)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use na::{FloatPnt, Pnt2, Vec2};
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    use super::*;
+
+    type Simulation = SpaceColonization<Pnt2<f32>, Vec2<f32>, ()>;
+
+    /// Grows a fixed scene and returns the position and parent of every node.
+    fn grow<S>(setup: S) -> Vec<(Pnt2<f32>, NodeIdx)>
+        where S: Fn(&mut Simulation)
+    {
+        grow_with(setup, |sc| sc.next())
+    }
+
+    /// Like ```grow```, but performs each iteration with ```step```.
+    fn grow_with<S, N>(setup: S, step: N) -> Vec<(Pnt2<f32>, NodeIdx)>
+        where S: Fn(&mut Simulation),
+              N: Fn(&mut Simulation) -> Option<IterationReport>
+    {
+        let mut sc = SpaceColonization::new(SqDist::from_dist(0.2),
+                                            SqDist::from_dist(0.05),
+                                            100,
+                                            10,
+                                            0.02);
+        setup(&mut sc);
+
+        sc.add_root_node(Pnt2::new(0.5, 0.0)).unwrap();
+        sc.add_root_node(Pnt2::new(0.0, 0.5)).unwrap();
+        sc.add_root_node(Pnt2::new(1.0, 1.0)).unwrap();
+
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        for _ in 0..500 {
+            sc.add_default_attractor(Pnt2::new(rng.gen::<f32>(), rng.gen::<f32>()));
+        }
+        // Attractors reaching further than the grid's cell size.
+        for &(x, y, attract_dist) in [(0.5, 0.5, SqDist::from_dist(0.6)),
+                                      (0.9, 0.1, SqDist(::std::f32::INFINITY))]
+                                         .iter() {
+            sc.add_attractor(Attractor {
+                attract_dist: attract_dist,
+                connect_dist: SqDist::from_dist(0.05),
+                strength: 1.0,
+                position: Pnt2::new(x, y),
+                information: (),
+                connect_action: ConnectAction::KillAttractor,
+                active_from_iteration: 0,
+                not_for_root: None,
+                not_for_connecting_root: None,
+            });
+        }
+
+        for _ in 0..200 {
+            if step(&mut sc).is_none() {
+                break;
+            }
+        }
+        sc.nodes.iter().map(|node| (node.position, node.parent)).collect()
+    }
+
+    #[test]
+    fn grid_grows_the_same_nodes_as_brute_force() {
+        let brute_force = grow(|sc| sc.set_node_search(NodeSearch::BruteForce));
+        let grid = grow(|sc| sc.set_node_search(NodeSearch::Grid));
+        assert!(brute_force.len() > 100);
+        assert_eq!(brute_force, grid);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_search_grows_the_same_nodes_as_serial() {
+        for &search in [NodeSearch::BruteForce, NodeSearch::Grid].iter() {
+            let serial = grow(|sc| sc.set_node_search(search));
+            let parallel = grow_with(|sc| sc.set_node_search(search), |sc| sc.next_parallel());
+            assert_eq!(serial, parallel);
+        }
+    }
+
+    /// Returns true if a node of the tree rooted at ```root``` is closer
+    /// to ```position``` than ```dist```.
+    fn tree_reaches(sc: &Simulation, root: NodeIdx, position: &Pnt2<f32>, dist: f32) -> bool {
+        sc.nodes().any(|n| n.root == root && n.position.dist(position) < dist)
+    }
+
+    #[test]
+    fn closed_venation_joins_nodes_once_all_of_them_connect() {
+        let mut sc: Simulation = SpaceColonization::new(SqDist::from_dist(0.6),
+                                                        SqDist::from_dist(0.15),
+                                                        100,
+                                                        10,
+                                                        0.1);
+        sc.set_venation(Venation::Closed);
+        // Both roots are in the relative neighborhood of the attractor,
+        // but the first is much closer to it.
+        let near = sc.add_root_node(Pnt2::new(0.3, 0.0)).unwrap();
+        let far = sc.add_root_node(Pnt2::new(1.0, 0.0)).unwrap();
+        let ap = Pnt2::new(0.5, 0.0);
+        sc.add_default_attractor(ap);
+
+        let mut waited = 0;
+        let mut joined = 0;
+        loop {
+            let near_connects = tree_reaches(&sc, near, &ap, 0.15);
+            let far_connects = tree_reaches(&sc, far, &ap, 0.15);
+            let report = match sc.next() {
+                Some(report) => report,
+                None => break,
+            };
+            if near_connects && far_connects {
+                assert_eq!(report.joined_nodes, 1);
+                assert_eq!(report.killed_attractors, 1);
+                joined += 1;
+            } else {
+                assert_eq!(report.joined_nodes, 0);
+                assert!(sc.nodes().all(|n| n.extra_parents.is_empty()));
+                if near_connects {
+                    waited += 1;
+                }
+            }
+        }
+        assert!(waited > 0);
+        assert_eq!(joined, 1);
+
+        // The two nodes within connect distance are joined, one being an
+        // extra parent of the other.
+        let connected: Vec<NodeIdx> = sc.nodes()
+                                        .filter(|n| n.position.dist(&ap) < 0.15)
+                                        .map(|n| n.idx)
+                                        .collect();
+        assert_eq!(connected.len(), 2);
+        let joins: Vec<(NodeIdx, NodeIdx)> = sc.nodes()
+                                               .flat_map(|n| {
+                                                   n.node
+                                                    .extra_parents
+                                                    .iter()
+                                                    .map(move |&p| (n.idx, p))
+                                               })
+                                               .collect();
+        assert!(joins == vec![(connected[0], connected[1])] ||
+                joins == vec![(connected[1], connected[0])],
+                "{:?}",
+                joins);
+    }
+
+    #[test]
+    fn anastomosis_joins_two_trees_once() {
+        let mut sc: Simulation = SpaceColonization::new(SqDist::from_dist(2.0),
+                                                        SqDist::from_dist(0.05),
+                                                        100,
+                                                        10,
+                                                        0.1);
+        sc.set_merge_dist(Some(SqDist::from_dist(0.15)));
+        let a = sc.add_root_node(Pnt2::new(0.0, 0.0)).unwrap();
+        let b = sc.add_root_node(Pnt2::new(1.0, 0.0)).unwrap();
+        // Each tree grows towards the other's root, passing it closely.
+        for &(x, y, other) in [(0.9, 0.1, b), (0.1, -0.1, a)].iter() {
+            sc.add_attractor(Attractor {
+                attract_dist: SqDist::from_dist(2.0),
+                connect_dist: SqDist::from_dist(0.05),
+                strength: 1.0,
+                position: Pnt2::new(x, y),
+                information: (),
+                connect_action: ConnectAction::KillAttractor,
+                active_from_iteration: 0,
+                not_for_root: Some(other),
+                not_for_connecting_root: None,
+            });
+        }
+
+        let mut new_junctions = 0;
+        for _ in 0..50 {
+            match sc.next() {
+                Some(report) => new_junctions += report.new_junctions,
+                None => break,
+            }
+        }
+        assert!(sc.nodes().count() > 10);
+        assert_eq!(new_junctions, 1);
+        assert_eq!(sc.junctions().len(), 1);
+
+        let junction = sc.junctions()[0];
+        assert!(junction.joins(a, b));
+        assert!(junction.joins(b, a));
+        assert!(!junction.joins(a, a));
+        assert!(!junction.joins(b, b));
+        assert_eq!(sc.node(junction.node).unwrap().root, junction.root);
+        assert_eq!(sc.node(junction.other).unwrap().root, junction.other_root);
+        assert!(junction.root != junction.other_root);
+        assert_eq!(sc.node(junction.node).unwrap().extra_parents,
+                   vec![junction.other]);
+        assert_eq!(sc.nodes().filter(|n| !n.extra_parents.is_empty()).count(), 1);
+    }
+
+    /// Two trees built by hand:
+    ///
+    /// ```text
+    ///        0            6
+    ///      /   \          |
+    ///     1     2         7
+    ///    / \    |
+    ///   3   4   5
+    ///   |
+    ///   8
+    /// ```
+    fn forest() -> Simulation {
+        let mut sc: Simulation = SpaceColonization::new(SqDist::from_dist(0.2),
+                                                        SqDist::from_dist(0.05),
+                                                        100,
+                                                        10,
+                                                        0.02);
+        let parents = [None, Some(0), Some(0), Some(1), Some(1), Some(2), None, Some(6), Some(3)];
+        for (i, &parent) in parents.iter().enumerate() {
+            let position = Pnt2::new(i as f32, 0.0);
+            let idx = match parent {
+                Some(parent) => sc.add_child_node(position, NodeIdx(parent)).unwrap(),
+                None => sc.add_root_node(position).unwrap(),
+            };
+            assert_eq!(idx, NodeIdx(i as u32));
+        }
+        sc
+    }
+
+    fn indices(indices: &[u32]) -> Vec<NodeIdx> {
+        indices.iter().map(|&i| NodeIdx(i)).collect()
+    }
+
+    #[test]
+    fn parents_and_children() {
+        let sc = forest();
+        assert_eq!(sc.parent(NodeIdx(0)), Ok(None));
+        assert_eq!(sc.parent(NodeIdx(8)), Ok(Some(NodeIdx(3))));
+        assert_eq!(sc.children(NodeIdx(1)), Ok(&indices(&[3, 4])[..]));
+        assert!(sc.children(NodeIdx(7)).unwrap().is_empty());
+        assert_eq!(sc.roots().map(|n| n.idx).collect::<Vec<_>>(), indices(&[0, 6]));
+    }
+
+    #[test]
+    fn subtree_iterators_visit_in_depth_and_breadth_first_order() {
+        let sc = forest();
+        assert_eq!(sc.subtree_dfs(NodeIdx(0)).unwrap().collect::<Vec<_>>(),
+                   indices(&[0, 1, 3, 8, 4, 2, 5]));
+        assert_eq!(sc.subtree_bfs(NodeIdx(0)).unwrap().collect::<Vec<_>>(),
+                   indices(&[0, 1, 2, 3, 4, 5, 8]));
+        assert_eq!(sc.subtree_dfs(NodeIdx(1)).unwrap().collect::<Vec<_>>(),
+                   indices(&[1, 3, 8, 4]));
+        assert_eq!(sc.subtree_bfs(NodeIdx(6)).unwrap().collect::<Vec<_>>(),
+                   indices(&[6, 7]));
+    }
+
+    #[test]
+    fn path_to_root_ends_at_the_root() {
+        let sc = forest();
+        assert_eq!(sc.path_to_root(NodeIdx(8)).unwrap().collect::<Vec<_>>(),
+                   indices(&[8, 3, 1, 0]));
+        assert_eq!(sc.path_to_root(NodeIdx(6)).unwrap().collect::<Vec<_>>(),
+                   indices(&[6]));
+    }
+
+    #[test]
+    fn lowest_common_ancestor_of_siblings_ancestors_and_other_trees() {
+        let sc = forest();
+        let lca = |a, b| sc.lowest_common_ancestor(NodeIdx(a), NodeIdx(b)).unwrap();
+        // Siblings and cousins.
+        assert_eq!(lca(3, 4), Some(NodeIdx(1)));
+        assert_eq!(lca(8, 5), Some(NodeIdx(0)));
+        // Ancestor and descendant, in both orders.
+        assert_eq!(lca(1, 8), Some(NodeIdx(1)));
+        assert_eq!(lca(8, 0), Some(NodeIdx(0)));
+        assert_eq!(lca(4, 4), Some(NodeIdx(4)));
+        // Different trees.
+        assert_eq!(lca(8, 7), None);
+        assert_eq!(lca(0, 6), None);
+    }
+
+    #[test]
+    fn topology_accessors_reject_invalid_indices() {
+        let mut sc = forest();
+        let invalid = NodeIdx(9);
+        let err = Some(ColonizationError::InvalidNodeIdx(invalid));
+        assert_eq!(sc.node(invalid).err(), err);
+        assert_eq!(sc.parent(invalid).err(), err);
+        assert_eq!(sc.children(invalid).err(), err);
+        assert_eq!(sc.subtree_dfs(invalid).err(), err);
+        assert_eq!(sc.subtree_bfs(invalid).err(), err);
+        assert_eq!(sc.path_to_root(invalid).err(), err);
+        assert_eq!(sc.lowest_common_ancestor(NodeIdx(0), invalid).err(), err);
+        assert_eq!(sc.lowest_common_ancestor(invalid, NodeIdx(0)).err(), err);
+        assert_eq!(sc.add_child_node(Pnt2::new(0.0, 0.0), invalid).err(), err);
+        assert_eq!(sc.node_count(), 9);
     }
 }
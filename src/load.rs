@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+#[cfg(feature = "png")]
+use png;
+
+/// Error reading one of the supported file formats.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+
+    /// The content does not follow the file format.
+    Parse(String),
+
+    /// The file is valid, but uses a feature that is not supported.
+    Unsupported(String),
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> LoadError {
+        LoadError::Io(err)
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<png::DecodingError> for LoadError {
+    fn from(err: png::DecodingError) -> LoadError {
+        match err {
+            png::DecodingError::IoError(err) => LoadError::Io(err),
+            other => LoadError::Parse(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref err) => write!(f, "I/O error: {}", err),
+            LoadError::Parse(ref msg) => write!(f, "parse error: {}", msg),
+            LoadError::Unsupported(ref msg) => write!(f, "unsupported: {}", msg),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            LoadError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
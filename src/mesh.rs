@@ -0,0 +1,471 @@
+//! Closed triangle meshes as envelopes and obstacles.
+//!
+//! A ```TriangleMesh``` loaded from a Wavefront OBJ file can be used to
+//! sample attractors inside of it (it is an ```Envelope```), as a solid
+//! obstacle, or, wrapped into a ```Containment```, to keep all nodes
+//! inside of it. Only 3D is supported.
+
+use na::{Pnt3, Vec3};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use load::LoadError;
+use obstacle::Obstacle;
+use sampling::Envelope;
+
+type V3 = [f32; 3];
+
+fn sub(a: &V3, b: &V3) -> V3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: &V3, b: &V3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &V3, b: &V3) -> V3 {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn to_v3(p: &Pnt3<f32>) -> V3 {
+    [p.x, p.y, p.z]
+}
+
+/// Leaves of the BVH hold at most this many triangles.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Directions of the rays used by the inside test. Chosen to be unlikely
+/// to hit edges or vertices of axis-aligned geometry exactly.
+const RAY_DIRECTIONS: [V3; 3] = [[0.4319, 0.6207, 0.6543],
+                                 [-0.7927, 0.2254, 0.5664],
+                                 [0.1878, -0.9067, 0.3777]];
+
+#[derive(Debug, Clone)]
+enum BvhContent {
+    /// Range within ```TriangleMesh::order```.
+    Leaf(usize, usize),
+    /// Indices of the child nodes.
+    Inner(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    min: V3,
+    max: V3,
+    content: BvhContent,
+}
+
+/// A triangle mesh with a bounding volume hierarchy.
+#[derive(Debug, Clone)]
+pub struct TriangleMesh {
+    vertices: Vec<V3>,
+    triangles: Vec<[usize; 3]>,
+
+    /// Triangle indices, ordered so that every BVH leaf covers a range.
+    order: Vec<usize>,
+    bvh: Vec<BvhNode>,
+}
+
+impl TriangleMesh {
+    /// Returns an error if a triangle refers to a missing vertex, a vertex
+    /// has a coordinate that is not finite, or there are no triangles.
+    pub fn new(vertices: Vec<Pnt3<f32>>,
+               triangles: Vec<[usize; 3]>)
+               -> Result<TriangleMesh, LoadError> {
+        if triangles.is_empty() {
+            return Err(LoadError::Parse("mesh without triangles".to_string()));
+        }
+        if let Some(&i) = triangles.iter().flat_map(|t| t.iter()).find(|&&i| i >= vertices.len()) {
+            return Err(LoadError::Parse(format!("vertex index {} out of range", i)));
+        }
+        if let Some(i) = vertices.iter()
+                                 .position(|v| !(v.x.is_finite() && v.y.is_finite() &&
+                                                 v.z.is_finite())) {
+            return Err(LoadError::Parse(format!("vertex {} is not finite", i)));
+        }
+
+        let mut mesh = TriangleMesh {
+            vertices: vertices.iter().map(to_v3).collect(),
+            order: (0..triangles.len()).collect(),
+            triangles: triangles,
+            bvh: Vec::new(),
+        };
+        let len = mesh.order.len();
+        mesh.build_bvh(0, len);
+        Ok(mesh)
+    }
+
+    /// Reads the vertices (```v```) and faces (```f```) of an OBJ file.
+    /// Faces with more than three vertices are triangulated as fans. All
+    /// other statements are ignored.
+    pub fn read_obj<R: BufRead>(reader: R) -> Result<TriangleMesh, LoadError> {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let mut coords = [0.0; 3];
+                    for c in coords.iter_mut() {
+                        *c = match tokens.next().map(|t| t.parse::<f32>()) {
+                            Some(Ok(value)) if value.is_finite() => value,
+                            _ => {
+                                return Err(LoadError::Parse(format!("line {}: invalid vertex",
+                                                                    n + 1)))
+                            }
+                        };
+                    }
+                    vertices.push(Pnt3::new(coords[0], coords[1], coords[2]));
+                }
+                Some("f") => {
+                    let mut face = Vec::new();
+                    for token in tokens {
+                        // Only the vertex index of "v/vt/vn" is of interest.
+                        let index = token.split('/').next().unwrap_or("");
+                        let index = match index.parse::<i64>() {
+                            Ok(i) if i > 0 => (i - 1) as usize,
+                            Ok(i) if i < 0 && i.unsigned_abs() <= vertices.len() as u64 => {
+                                vertices.len() - i.unsigned_abs() as usize
+                            }
+                            _ => {
+                                return Err(LoadError::Parse(format!("line {}: invalid face \
+                                                                     index {:?}",
+                                                                    n + 1,
+                                                                    token)))
+                            }
+                        };
+                        face.push(index);
+                    }
+                    if face.len() < 3 {
+                        return Err(LoadError::Parse(format!("line {}: face with less than \
+                                                             three vertices",
+                                                            n + 1)));
+                    }
+                    for i in 1..face.len() - 1 {
+                        triangles.push([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        TriangleMesh::new(vertices, triangles)
+    }
+
+    pub fn open_obj<P: AsRef<Path>>(path: P) -> Result<TriangleMesh, LoadError> {
+        TriangleMesh::read_obj(BufReader::new(File::open(path)?))
+    }
+
+    fn triangle(&self, t: usize) -> (&V3, &V3, &V3) {
+        let [a, b, c] = self.triangles[t];
+        (&self.vertices[a], &self.vertices[b], &self.vertices[c])
+    }
+
+    fn centroid(&self, t: usize) -> V3 {
+        let (a, b, c) = self.triangle(t);
+        [(a[0] + b[0] + c[0]) / 3.0, (a[1] + b[1] + c[1]) / 3.0, (a[2] + b[2] + c[2]) / 3.0]
+    }
+
+    /// Builds the subtree over ```order[start..end]``` and returns its index.
+    fn build_bvh(&mut self, start: usize, end: usize) -> usize {
+        let mut min = [::std::f32::INFINITY; 3];
+        let mut max = [::std::f32::NEG_INFINITY; 3];
+        for &t in self.order[start..end].iter() {
+            let (a, b, c) = self.triangle(t);
+            for v in [a, b, c].iter() {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(v[axis]);
+                    max[axis] = max[axis].max(v[axis]);
+                }
+            }
+        }
+
+        let idx = self.bvh.len();
+        self.bvh.push(BvhNode {
+            min: min,
+            max: max,
+            content: BvhContent::Leaf(start, end),
+        });
+        if end - start <= MAX_LEAF_SIZE {
+            return idx;
+        }
+
+        // Split at the median centroid along the longest axis.
+        let extent = sub(&max, &min);
+        let axis = if extent[0] >= extent[1] && extent[0] >= extent[2] {
+            0
+        } else if extent[1] >= extent[2] {
+            1
+        } else {
+            2
+        };
+        let mut order: Vec<usize> = self.order[start..end].to_vec();
+        order.sort_by(|&a, &b| {
+            self.centroid(a)[axis].partial_cmp(&self.centroid(b)[axis]).unwrap()
+        });
+        self.order[start..end].copy_from_slice(&order);
+
+        let middle = start + (end - start) / 2;
+        let left = self.build_bvh(start, middle);
+        let right = self.build_bvh(middle, end);
+        self.bvh[idx].content = BvhContent::Inner(left, right);
+        idx
+    }
+
+    /// Calls ```visitor``` with every triangle whose bounding box may be
+    /// hit by the ray ```origin + t * direction``` for t in [0, max_t].
+    fn visit_ray<V>(&self, origin: &V3, direction: &V3, max_t: f32, visitor: &mut V)
+        where V: FnMut(usize)
+    {
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let node = &self.bvh[idx];
+            if !ray_hits_box(origin, direction, max_t, &node.min, &node.max) {
+                continue;
+            }
+            match node.content {
+                BvhContent::Leaf(start, end) => {
+                    for &t in self.order[start..end].iter() {
+                        visitor(t);
+                    }
+                }
+                BvhContent::Inner(left, right) => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+    }
+
+    /// Returns the ray parameter where the ray hits triangle ```t```
+    /// (Möller-Trumbore).
+    fn intersect_triangle(&self, t: usize, origin: &V3, direction: &V3) -> Option<f32> {
+        let (a, b, c) = self.triangle(t);
+        let e1 = sub(b, a);
+        let e2 = sub(c, a);
+        let p = cross(direction, &e2);
+        let det = dot(&e1, &p);
+        if det.abs() < 1.0e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = sub(origin, a);
+        let u = dot(&s, &p) * inv_det;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+        let q = cross(&s, &e1);
+        let v = dot(direction, &q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        Some(dot(&e2, &q) * inv_det)
+    }
+
+    fn count_crossings(&self, origin: &V3, direction: &V3) -> usize {
+        let mut crossings = 0;
+        self.visit_ray(origin,
+                       direction,
+                       ::std::f32::INFINITY,
+                       &mut |t| {
+                           if let Some(hit) = self.intersect_triangle(t, origin, direction) {
+                               if hit > 0.0 {
+                                   crossings += 1;
+                               }
+                           }
+                       });
+        crossings
+    }
+
+    /// Returns true if ```position``` lies inside the mesh. Casts three
+    /// rays and counts their crossings of the surface. The majority vote
+    /// makes the test robust against rays that graze edges.
+    pub fn contains_point(&self, position: &Pnt3<f32>) -> bool {
+        let root = &self.bvh[0];
+        let origin = to_v3(position);
+        if (0..3).any(|axis| origin[axis] < root.min[axis] || origin[axis] > root.max[axis]) {
+            return false;
+        }
+        let inside = RAY_DIRECTIONS.iter()
+                                   .filter(|d| self.count_crossings(&origin, d) % 2 == 1)
+                                   .count();
+        inside >= 2
+    }
+
+    /// Returns where the segment from ```from``` to ```to``` first crosses
+    /// the surface, as segment parameter in [0, 1] and the normal of the
+    /// crossed triangle. The normal points against the segment.
+    pub fn intersect_surface(&self, from: &Pnt3<f32>, to: &Pnt3<f32>) -> Option<(f32, Vec3<f32>)> {
+        let origin = to_v3(from);
+        let direction = sub(&to_v3(to), &origin);
+        let mut first: Option<(f32, usize)> = None;
+        self.visit_ray(&origin,
+                       &direction,
+                       1.0,
+                       &mut |t| {
+                           if let Some(hit) = self.intersect_triangle(t, &origin, &direction) {
+                               if hit >= 0.0 && hit <= 1.0 && first.map_or(true, |f| hit < f.0) {
+                                   first = Some((hit, t));
+                               }
+                           }
+                       });
+
+        first.map(|(hit, t)| {
+            let (a, b, c) = self.triangle(t);
+            let mut normal = cross(&sub(b, a), &sub(c, a));
+            if dot(&normal, &direction) > 0.0 {
+                normal = [-normal[0], -normal[1], -normal[2]];
+            }
+            let len = dot(&normal, &normal).sqrt();
+            (hit, Vec3::new(normal[0] / len, normal[1] / len, normal[2] / len))
+        })
+    }
+
+    /// The minimum and maximum corner of the bounding box.
+    pub fn bounding_box(&self) -> (Pnt3<f32>, Pnt3<f32>) {
+        let root = &self.bvh[0];
+        (Pnt3::new(root.min[0], root.min[1], root.min[2]),
+         Pnt3::new(root.max[0], root.max[1], root.max[2]))
+    }
+}
+
+/// Slab test of the ray ```origin + t * direction```, t in [0, max_t].
+fn ray_hits_box(origin: &V3, direction: &V3, max_t: f32, min: &V3, max: &V3) -> bool {
+    let mut t_enter = 0.0f32;
+    let mut t_exit = max_t;
+    for axis in 0..3 {
+        if direction[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return false;
+            }
+            continue;
+        }
+        let inv = 1.0 / direction[axis];
+        let t0 = (min[axis] - origin[axis]) * inv;
+        let t1 = (max[axis] - origin[axis]) * inv;
+        t_enter = t_enter.max(t0.min(t1));
+        t_exit = t_exit.min(t0.max(t1));
+        if t_enter > t_exit {
+            return false;
+        }
+    }
+    true
+}
+
+/// The segment direction reversed. Used as surface normal for segments
+/// starting inside of an obstacle, which makes them impossible to deflect.
+fn backwards(from: &Pnt3<f32>, to: &Pnt3<f32>) -> Vec3<f32> {
+    let d = sub(&to_v3(from), &to_v3(to));
+    let len = dot(&d, &d).sqrt().max(::std::f32::MIN_POSITIVE);
+    Vec3::new(d[0] / len, d[1] / len, d[2] / len)
+}
+
+impl Envelope<Pnt3<f32>> for TriangleMesh {
+    fn contains(&self, position: &Pnt3<f32>) -> bool {
+        self.contains_point(position)
+    }
+
+    fn bounds(&self) -> (Pnt3<f32>, Pnt3<f32>) {
+        self.bounding_box()
+    }
+}
+
+/// The mesh as a solid obstacle.
+impl Obstacle<Pnt3<f32>, Vec3<f32>> for TriangleMesh {
+    fn contains(&self, position: &Pnt3<f32>) -> bool {
+        self.contains_point(position)
+    }
+
+    fn intersect_segment(&self, from: &Pnt3<f32>, to: &Pnt3<f32>) -> Option<Vec3<f32>> {
+        if self.contains_point(from) {
+            return Some(backwards(from, to));
+        }
+        self.intersect_surface(from, to).map(|(_, normal)| normal)
+    }
+}
+
+/// Turns a mesh inside out: everything outside of it is an obstacle.
+/// Keeps nodes inside the mesh and ignores attractors outside of it.
+#[derive(Debug, Clone)]
+pub struct Containment(pub TriangleMesh);
+
+impl Obstacle<Pnt3<f32>, Vec3<f32>> for Containment {
+    fn contains(&self, position: &Pnt3<f32>) -> bool {
+        !self.0.contains_point(position)
+    }
+
+    fn intersect_segment(&self, from: &Pnt3<f32>, to: &Pnt3<f32>) -> Option<Vec3<f32>> {
+        if !self.0.contains_point(from) {
+            return Some(backwards(from, to));
+        }
+        self.0.intersect_surface(from, to).map(|(_, normal)| normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use na::Pnt3;
+    use load::LoadError;
+    use super::*;
+
+    /// A unit cube with quad faces, referring to vertices by positive and
+    /// negative indices, with and without texture and normal indices.
+    const CUBE: &'static str = "# unit cube\n\
+                                o cube\n\
+                                v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+                                v 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1\n\
+                                vn 0 0 1\n\
+                                f 1 2 3 4\n\
+                                f -4 -3 -2 -1\n\
+                                f 1 2 6 5\n\
+                                f 2/1/1 3//1 7/2 6\n\
+                                f -6 -5 -1 -2\n\
+                                f 4 1 5 8\n";
+
+    fn cube() -> TriangleMesh {
+        TriangleMesh::read_obj(CUBE.as_bytes()).unwrap()
+    }
+
+    fn assert_parse_error(obj: &str) {
+        match TriangleMesh::read_obj(obj.as_bytes()) {
+            Err(LoadError::Parse(_)) => {}
+            other => panic!("expected a parse error for {:?}, got {:?}", obj, other),
+        }
+    }
+
+    #[test]
+    fn obj_cube_contains_points_inside() {
+        let cube = cube();
+        assert_eq!(cube.bounding_box(),
+                   (Pnt3::new(0.0, 0.0, 0.0), Pnt3::new(1.0, 1.0, 1.0)));
+        assert!(cube.contains_point(&Pnt3::new(0.5, 0.5, 0.5)));
+        assert!(cube.contains_point(&Pnt3::new(0.2, 0.7, 0.9)));
+    }
+
+    #[test]
+    fn obj_cube_does_not_contain_points_outside() {
+        let cube = cube();
+        assert!(!cube.contains_point(&Pnt3::new(1.5, 0.5, 0.5)));
+        assert!(!cube.contains_point(&Pnt3::new(0.5, -0.2, 0.5)));
+        assert!(!cube.contains_point(&Pnt3::new(2.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn obj_cube_is_entered_through_its_bottom() {
+        let (t, normal) = cube().intersect_surface(&Pnt3::new(0.3, 0.6, -1.0),
+                                                   &Pnt3::new(0.3, 0.6, 0.5))
+                                .unwrap();
+        assert!((t - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(normal, Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn obj_rejects_invalid_face_indices() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        assert_parse_error(&format!("{}f 1 2 0\n", vertices));
+        assert_parse_error(&format!("{}f 1 2 -4\n", vertices));
+        assert_parse_error(&format!("{}f 1 2 -9223372036854775808\n", vertices));
+        assert_parse_error(&format!("{}f 1 2 4\n", vertices));
+        assert_parse_error(&format!("{}f 1 2\n", vertices));
+    }
+}
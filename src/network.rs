@@ -0,0 +1,210 @@
+//! Directed networks grown between positions, e.g. neural topologies.
+//!
+//! Every position is the root of a tree and, surrounded by a few
+//! attractors, the target of all the other trees. When a tree reaches
+//! the attractors of a target, an edge from its root to the target is
+//! recorded.
+
+use na::{FloatPnt, FloatVec};
+use num::Zero;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{self, Write};
+use sampling::{self, Sphere};
+use {Attractor, ColonizationError, ConnectAction, ConnectPolicy, Coords, InformationSet, NodeIdx,
+     SpaceColonization, SqDist};
+
+/// Parameters of ```Network::build```.
+#[derive(Debug, Copy, Clone)]
+pub struct NetworkParams {
+    pub attract_dist: f32,
+    pub connect_dist: f32,
+    pub move_dist: f32,
+    pub max_length: u32,
+    pub max_branches: u32,
+
+    /// Number of attractors placed around each position.
+    pub attractors_per_node: usize,
+
+    /// Radius of the sphere around each position its attractors are
+    /// placed in.
+    pub target_radius: f32,
+
+    /// The simulation stops after this many iterations.
+    pub max_iterations: u32,
+}
+
+impl NetworkParams {
+    pub fn new(attract_dist: f32, connect_dist: f32, move_dist: f32) -> NetworkParams {
+        NetworkParams {
+            attract_dist: attract_dist,
+            connect_dist: connect_dist,
+            move_dist: move_dist,
+            max_length: 100,
+            max_branches: 10,
+            attractors_per_node: 4,
+            target_radius: connect_dist,
+            max_iterations: 1000,
+        }
+    }
+}
+
+/// A connection from the position ```source``` to ```target```, both
+/// indices into the positions the network was built from.
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub source: usize,
+    pub target: usize,
+
+    /// Sum of the segment lengths along ```path```.
+    pub length: f32,
+
+    /// Number of segments along ```path```.
+    pub hops: u32,
+
+    /// The nodes from the root at ```source``` to the node that reached
+    /// the attractors of ```target```.
+    pub path: Vec<NodeIdx>,
+}
+
+/// The nodes carry the indices of the targets they reached.
+pub type NetworkSimulation<P, F> = SpaceColonization<P, F, InformationSet<usize>>;
+
+pub struct Network<P, F>
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug
+{
+    edges: Vec<Edge>,
+    simulation: NetworkSimulation<P, F>,
+}
+
+impl<P, F> Network<P, F>
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug
+{
+    /// Grows trees from all ```positions``` towards each other, attracted
+    /// by the targeting attractors and the ```background``` attractors.
+    /// Of several connections between two positions, only the one with
+    /// the shortest path is kept. Fails if a position is not finite.
+    pub fn build<R: Rng>(positions: &[P],
+                         background: &[P],
+                         params: &NetworkParams,
+                         rng: &mut R)
+                         -> Result<Network<P, F>, ColonizationError> {
+        let mut sc = SpaceColonization::new(SqDist::from_dist(params.attract_dist),
+                                            SqDist::from_dist(params.connect_dist),
+                                            params.max_length,
+                                            params.max_branches,
+                                            params.move_dist);
+
+        // Connect target attractors with the closest node, not the oldest one.
+        sc.set_connect_policy(ConnectPolicy::Nearest);
+
+        let mut roots = HashMap::new();
+        for (target, &position) in positions.iter().enumerate() {
+            let root = sc.add_root_node(position)?;
+            roots.insert(root, target);
+
+            let around = Sphere {
+                center: position,
+                radius: params.target_radius,
+            };
+            for p in sampling::sample_points(&around, params.attractors_per_node, rng) {
+                sc.add_attractor(Attractor {
+                    attract_dist: SqDist::from_dist(params.attract_dist),
+                    connect_dist: SqDist::from_dist(params.connect_dist),
+                    strength: 1.0,
+                    position: p,
+                    information: InformationSet::single(target),
+                    connect_action: ConnectAction::DisableForConnectingRoot,
+                    active_from_iteration: 0,
+                    // A tree must not be attracted by its own target.
+                    not_for_root: Some(root),
+                    not_for_connecting_root: None,
+                });
+            }
+        }
+        for &p in background {
+            sc.add_default_attractor(p);
+        }
+
+        for _ in 0..params.max_iterations {
+            if sc.next().is_none() {
+                break;
+            }
+        }
+
+        let mut shortest: HashMap<(usize, usize), Edge> = HashMap::new();
+        for (i, node) in sc.nodes.iter().enumerate() {
+            let targets = match node.assigned_information {
+                Some(ref targets) if !node.is_root() => targets,
+                _ => continue,
+            };
+            let source = roots[&node.root];
+
+            let mut path = vec![NodeIdx(i as u32)];
+            let mut length = 0.0;
+            let mut current = node;
+            while !current.is_root() {
+                let parent = &sc.nodes[current.parent.0 as usize];
+                length += current.position.dist(&parent.position);
+                path.push(current.parent);
+                current = parent;
+            }
+            path.reverse();
+
+            for &target in targets.0.iter() {
+                let shorter = shortest.get(&(source, target)).map_or(true, |e| length < e.length);
+                if shorter {
+                    shortest.insert((source, target),
+                                    Edge {
+                                        source: source,
+                                        target: target,
+                                        length: length,
+                                        hops: node.length,
+                                        path: path.clone(),
+                                    });
+                }
+            }
+        }
+
+        let mut edges: Vec<Edge> = shortest.into_iter().map(|(_, edge)| edge).collect();
+        edges.sort_by(|a, b| (a.source, a.target).cmp(&(b.source, b.target)));
+
+        Ok(Network {
+            edges: edges,
+            simulation: sc,
+        })
+    }
+}
+
+impl<P, F> Network<P, F>
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug
+{
+    /// The edges, ordered by source and target.
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// The simulation the network was grown by.
+    pub fn simulation(&self) -> &NetworkSimulation<P, F> {
+        &self.simulation
+    }
+
+    /// Writes the network in the DOT format of Graphviz, weighted by
+    /// path length.
+    pub fn write_dot<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "digraph space {{")?;
+        for edge in self.edges.iter() {
+            writeln!(out,
+                     "{} -> {} [weight={}, hops={}];",
+                     edge.source,
+                     edge.target,
+                     edge.length,
+                     edge.hops)?;
+        }
+        writeln!(out, "}}")
+    }
+}
@@ -0,0 +1,299 @@
+use na::{Dot, FloatPnt, FloatVec, Norm, Pnt2, Vec2};
+use coords::Coords;
+
+/// A region of space that nodes must not grow into.
+pub trait Obstacle<P, F> {
+    /// Returns true if ```position``` lies inside the obstacle.
+    fn contains(&self, position: &P) -> bool;
+
+    /// If the segment from ```from``` to ```to``` enters the obstacle,
+    /// returns the outward surface normal where it enters. If ```from```
+    /// already lies inside, returns the normal of the nearest surface.
+    fn intersect_segment(&self, from: &P, to: &P) -> Option<F>;
+}
+
+/// What happens to a new node whose segment enters an obstacle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ObstacleResponse {
+    /// The node is not grown.
+    Reject,
+
+    /// The segment is turned along the surface of the obstacle, keeping
+    /// its length. If it still enters an obstacle, the node is not grown.
+    Deflect,
+}
+
+/// A solid sphere (a disc in 2D).
+#[derive(Debug, Copy, Clone)]
+pub struct SphereObstacle<P> {
+    pub center: P,
+    pub radius: f32,
+}
+
+impl<P, F> Obstacle<P, F> for SphereObstacle<P>
+    where P: FloatPnt<f32, F>,
+          F: FloatVec<f32> + Copy
+{
+    fn contains(&self, position: &P) -> bool {
+        position.sqdist(&self.center) < self.radius * self.radius
+    }
+
+    fn intersect_segment(&self, from: &P, to: &P) -> Option<F> {
+        let offset = *from - self.center;
+        if self.contains(from) {
+            return Some(offset.normalize());
+        }
+
+        // Solve |offset + t * d|^2 = r^2 for the entering t.
+        let d = *to - *from;
+        let a = d.dot(&d);
+        let b = 2.0 * offset.dot(&d);
+        let c = offset.dot(&offset) - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if a == 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        let t = (-b - discriminant.sqrt()) / (2.0 * a);
+        if t >= 0.0 && t <= 1.0 {
+            Some((offset + d * t).normalize())
+        } else {
+            None
+        }
+    }
+}
+
+/// A solid axis-aligned box.
+#[derive(Debug, Copy, Clone)]
+pub struct BoxObstacle<P> {
+    pub min: P,
+    pub max: P,
+}
+
+fn unit_axis<F: Coords>(axis: usize, sign: f32) -> F {
+    let mut coords = [0.0; 3];
+    coords[axis] = sign;
+    F::from_coords(&coords[..F::dimensions()])
+}
+
+impl<P, F> Obstacle<P, F> for BoxObstacle<P>
+    where P: FloatPnt<f32, F> + Coords,
+          F: FloatVec<f32> + Coords + Copy
+{
+    fn contains(&self, position: &P) -> bool {
+        (0..P::dimensions()).all(|axis| {
+            let x = position.coord(axis);
+            x > self.min.coord(axis) && x < self.max.coord(axis)
+        })
+    }
+
+    fn intersect_segment(&self, from: &P, to: &P) -> Option<F> {
+        if self.contains(from) {
+            // The face nearest to ```from```.
+            let mut nearest = (::std::f32::INFINITY, 0, 1.0);
+            for axis in 0..P::dimensions() {
+                let x = from.coord(axis);
+                let to_min = x - self.min.coord(axis);
+                let to_max = self.max.coord(axis) - x;
+                if to_min < nearest.0 {
+                    nearest = (to_min, axis, -1.0);
+                }
+                if to_max < nearest.0 {
+                    nearest = (to_max, axis, 1.0);
+                }
+            }
+            return Some(unit_axis(nearest.1, nearest.2));
+        }
+
+        // Slab test, keeping track of the face the segment enters through.
+        let mut t_enter = 0.0;
+        let mut t_exit = 1.0;
+        let mut normal = None;
+        for axis in 0..P::dimensions() {
+            let origin = from.coord(axis);
+            let d = to.coord(axis) - origin;
+            let (lo, hi) = (self.min.coord(axis), self.max.coord(axis));
+            if d == 0.0 {
+                if origin <= lo || origin >= hi {
+                    return None;
+                }
+                continue;
+            }
+            let t_lo = (lo - origin) / d;
+            let t_hi = (hi - origin) / d;
+            let (near, far, sign) = if t_lo < t_hi {
+                (t_lo, t_hi, -1.0)
+            } else {
+                (t_hi, t_lo, 1.0)
+            };
+            // A segment starting on a face enters through it at t = 0.
+            if near >= t_enter {
+                t_enter = near;
+                normal = Some(unit_axis(axis, sign));
+            }
+            if far < t_exit {
+                t_exit = far;
+            }
+            if t_enter >= t_exit {
+                return None;
+            }
+        }
+        normal
+    }
+}
+
+/// Blocks everything behind a plane (a line in 2D). The ```normal```
+/// points to the free side.
+#[derive(Debug, Copy, Clone)]
+pub struct HalfSpaceObstacle<P, F> {
+    pub point: P,
+    pub normal: F,
+}
+
+impl<P, F> Obstacle<P, F> for HalfSpaceObstacle<P, F>
+    where P: FloatPnt<f32, F>,
+          F: FloatVec<f32> + Copy
+{
+    fn contains(&self, position: &P) -> bool {
+        (*position - self.point).dot(&self.normal) < 0.0
+    }
+
+    fn intersect_segment(&self, from: &P, to: &P) -> Option<F> {
+        if self.contains(from) || self.contains(to) {
+            Some(self.normal.normalize())
+        } else {
+            None
+        }
+    }
+}
+
+/// A solid simple polygon in 2D. Vertices may be in either order.
+#[derive(Debug, Clone)]
+pub struct PolygonObstacle {
+    pub vertices: Vec<Pnt2<f32>>,
+}
+
+impl PolygonObstacle {
+    pub fn new(vertices: Vec<Pnt2<f32>>) -> PolygonObstacle {
+        PolygonObstacle { vertices: vertices }
+    }
+
+    fn edges<'a>(&'a self) -> Box<dyn Iterator<Item = (Pnt2<f32>, Pnt2<f32>)> + 'a> {
+        let n = self.vertices.len();
+        Box::new((0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n])))
+    }
+
+    /// The unit normal of the edge from ```a``` to ```b```, pointing to
+    /// the side of ```towards```.
+    fn edge_normal(a: Pnt2<f32>, b: Pnt2<f32>, towards: Pnt2<f32>) -> Vec2<f32> {
+        let edge = b - a;
+        let normal = Vec2::new(-edge.y, edge.x).normalize();
+        if (towards - a).dot(&normal) < 0.0 {
+            normal * -1.0
+        } else {
+            normal
+        }
+    }
+}
+
+/// Tests ```position``` against the polygon by the even-odd rule.
+pub fn polygon_contains(vertices: &[Pnt2<f32>], position: &Pnt2<f32>) -> bool {
+    let n = vertices.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+        if (a.y > position.y) != (b.y > position.y) {
+            let x = a.x + (position.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if position.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+impl Obstacle<Pnt2<f32>, Vec2<f32>> for PolygonObstacle {
+    fn contains(&self, position: &Pnt2<f32>) -> bool {
+        polygon_contains(&self.vertices, position)
+    }
+
+    fn intersect_segment(&self, from: &Pnt2<f32>, to: &Pnt2<f32>) -> Option<Vec2<f32>> {
+        if self.contains(from) {
+            // Leave through the nearest edge.
+            let mut nearest = None;
+            let mut nearest_dist = ::std::f32::INFINITY;
+            for (a, b) in self.edges() {
+                let edge = b - a;
+                let len = edge.sqnorm();
+                let t = if len > 0.0 {
+                    ((*from - a).dot(&edge) / len).max(0.0).min(1.0)
+                } else {
+                    0.0
+                };
+                let dist = from.sqdist(&(a + edge * t));
+                if dist < nearest_dist {
+                    nearest_dist = dist;
+                    // The outward normal points away from the inside point.
+                    nearest = Some(PolygonObstacle::edge_normal(a, b, *from) * -1.0);
+                }
+            }
+            return nearest;
+        }
+
+        // The first edge crossed by the segment.
+        let d = *to - *from;
+        let mut first = None;
+        let mut first_t = ::std::f32::INFINITY;
+        for (a, b) in self.edges() {
+            let e = b - a;
+            let denom = d.x * e.y - d.y * e.x;
+            if denom == 0.0 {
+                continue;
+            }
+            let w = a - *from;
+            let t = (w.x * e.y - w.y * e.x) / denom;
+            let u = (w.x * d.y - w.y * d.x) / denom;
+            if t >= 0.0 && t <= 1.0 && u >= 0.0 && u <= 1.0 && t < first_t {
+                first_t = t;
+                first = Some(PolygonObstacle::edge_normal(a, b, *from));
+            }
+        }
+        first
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use na::{Pnt2, Vec2};
+    use super::*;
+
+    fn unit_box() -> BoxObstacle<Pnt2<f32>> {
+        BoxObstacle {
+            min: Pnt2::new(0.0, 0.0),
+            max: Pnt2::new(1.0, 1.0),
+        }
+    }
+
+    fn intersect(from: (f32, f32), to: (f32, f32)) -> Option<Vec2<f32>> {
+        unit_box().intersect_segment(&Pnt2::new(from.0, from.1), &Pnt2::new(to.0, to.1))
+    }
+
+    #[test]
+    fn box_reports_the_face_a_segment_enters_through() {
+        assert_eq!(intersect((-0.5, 0.5), (0.5, 0.5)), Some(Vec2::new(-1.0, 0.0)));
+        assert_eq!(intersect((0.5, 1.5), (0.5, 0.5)), Some(Vec2::new(0.0, 1.0)));
+        assert_eq!(intersect((-0.5, 0.5), (-0.1, 0.5)), None);
+    }
+
+    #[test]
+    fn box_is_entered_by_a_segment_starting_on_a_face() {
+        assert_eq!(intersect((0.0, 0.5), (0.1, 0.5)), Some(Vec2::new(-1.0, 0.0)));
+        assert_eq!(intersect((1.0, 0.5), (0.9, 0.5)), Some(Vec2::new(1.0, 0.0)));
+        assert_eq!(intersect((0.5, 0.0), (0.5, 0.1)), Some(Vec2::new(0.0, -1.0)));
+    }
+
+    #[test]
+    fn box_is_not_entered_by_a_segment_leaving_or_sliding_along_a_face() {
+        assert_eq!(intersect((0.0, 0.5), (-0.1, 0.5)), None);
+        assert_eq!(intersect((0.0, 0.2), (0.0, 0.8)), None);
+    }
+}
@@ -0,0 +1,31 @@
+/// Restricts the attractors a node perceives to a cone around its
+/// growth direction, as in the tree model of Runions et al.
+///
+/// The axis of the cone is the direction of the segment leading to the
+/// node. Root nodes have no such segment and use ```root_direction```.
+#[derive(Debug, Copy, Clone)]
+pub struct Perception<F> {
+    /// Half the opening angle of the cone in radians. An angle of PI
+    /// perceives the full sphere.
+    pub half_angle: f32,
+
+    /// The axis of the cone of root nodes. With ```None```, roots
+    /// perceive attractors in all directions.
+    pub root_direction: Option<F>,
+}
+
+impl<F> Perception<F> {
+    pub fn new(half_angle: f32) -> Perception<F> {
+        Perception {
+            half_angle: half_angle,
+            root_direction: None,
+        }
+    }
+
+    pub fn with_root_direction(half_angle: f32, root_direction: F) -> Perception<F> {
+        Perception {
+            half_angle: half_angle,
+            root_direction: Some(root_direction),
+        }
+    }
+}
@@ -0,0 +1,659 @@
+//! Reading attractors from point-cloud files.
+//!
+//! Supported are whitespace separated XYZ files, CSV files and PLY files
+//! (ASCII and binary). Each point becomes a copy of a template attractor
+//! placed at the point. Optional columns override the ```strength```,
+//! ```attract_dist``` and ```connect_dist``` of the template. Distances in
+//! files are plain (not squared) distances.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use load::LoadError;
+use {Attractor, Coords, SqDist};
+
+/// Refers to a column by its position (starting at 0) or by its name in
+/// the header. XYZ files have no header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+/// Selects the columns holding the attributes of an attractor.
+#[derive(Debug, Clone)]
+pub struct Columns {
+    /// The x, y (and z) coordinates. For 2D points only the first two
+    /// are used.
+    pub position: Vec<Column>,
+    pub strength: Option<Column>,
+    pub attract_dist: Option<Column>,
+    pub connect_dist: Option<Column>,
+}
+
+impl Columns {
+    /// The position in the first three columns, no attributes.
+    pub fn xyz() -> Columns {
+        Columns::by_index(&[0, 1, 2])
+    }
+
+    /// The position in the first two columns, no attributes.
+    pub fn xy() -> Columns {
+        Columns::by_index(&[0, 1])
+    }
+
+    fn by_index(position: &[usize]) -> Columns {
+        Columns {
+            position: position.iter().map(|&i| Column::Index(i)).collect(),
+            strength: None,
+            attract_dist: None,
+            connect_dist: None,
+        }
+    }
+
+    /// Columns named ```x```, ```y``` and ```z```, plus
+    /// ```strength```, ```attract_dist``` and ```connect_dist``` if present.
+    /// This is the default for PLY files.
+    pub fn named() -> Columns {
+        Columns {
+            position: vec![Column::Name("x".to_string()),
+                           Column::Name("y".to_string()),
+                           Column::Name("z".to_string())],
+            strength: Some(Column::Name("strength".to_string())),
+            attract_dist: Some(Column::Name("attract_dist".to_string())),
+            connect_dist: Some(Column::Name("connect_dist".to_string())),
+        }
+    }
+}
+
+/// ```Columns``` resolved to column positions.
+struct Resolved {
+    position: Vec<usize>,
+    strength: Option<usize>,
+    attract_dist: Option<usize>,
+    connect_dist: Option<usize>,
+}
+
+impl Resolved {
+    /// Resolves ```columns``` against the column ```names```, if known.
+    /// Named attribute columns missing from the header are ignored, named
+    /// position columns are required.
+    fn new(columns: &Columns,
+           dims: usize,
+           names: Option<&[String]>)
+           -> Result<Resolved, LoadError> {
+        let find = |column: &Column| -> Result<Option<usize>, LoadError> {
+            match *column {
+                Column::Index(i) => Ok(Some(i)),
+                Column::Name(ref name) => {
+                    match names {
+                        Some(names) => Ok(names.iter().position(|n| n == name)),
+                        None => {
+                            Err(LoadError::Parse(format!("no header to look up column {:?}",
+                                                         name)))
+                        }
+                    }
+                }
+            }
+        };
+
+        if columns.position.len() < dims {
+            return Err(LoadError::Parse(format!("{} position columns given, {} needed",
+                                                columns.position.len(),
+                                                dims)));
+        }
+        let mut position = Vec::with_capacity(dims);
+        for column in columns.position[..dims].iter() {
+            match find(column)? {
+                Some(i) => position.push(i),
+                None => return Err(LoadError::Parse(format!("missing column {:?}", column))),
+            }
+        }
+        let optional = |column: &Option<Column>| -> Result<Option<usize>, LoadError> {
+            match *column {
+                Some(ref column) => find(column),
+                None => Ok(None),
+            }
+        };
+
+        Ok(Resolved {
+            position: position,
+            strength: optional(&columns.strength)?,
+            attract_dist: optional(&columns.attract_dist)?,
+            connect_dist: optional(&columns.connect_dist)?,
+        })
+    }
+
+    /// Returns true if column ```i``` holds one of the selected values.
+    fn uses(&self, i: usize) -> bool {
+        self.position.contains(&i) || self.strength == Some(i) ||
+        self.attract_dist == Some(i) || self.connect_dist == Some(i)
+    }
+
+    /// Parses the selected columns of a text row. The other columns may
+    /// hold anything, e.g. labels, and are read as 0.
+    fn parse_row<'a, T>(&self, tokens: T, line: usize) -> Result<Vec<f64>, LoadError>
+        where T: Iterator<Item = &'a str>
+    {
+        tokens.enumerate()
+              .map(|(i, token)| {
+                  if self.uses(i) {
+                      parse_number(token, line)
+                  } else {
+                      Ok(0.0)
+                  }
+              })
+              .collect()
+    }
+
+    /// Creates a copy of ```template``` from the selected values of
+    /// ```row```, which must be finite also as ```f32```.
+    fn attractor<P, I>(&self,
+                       row: &[f64],
+                       record: usize,
+                       template: &Attractor<P, I>)
+                       -> Result<Attractor<P, I>, LoadError>
+        where P: Coords,
+              I: Clone
+    {
+        let get = |i: usize| -> Result<f32, LoadError> {
+            match row.get(i).map(|&value| value as f32) {
+                Some(value) if value.is_finite() => Ok(value),
+                Some(value) => {
+                    Err(LoadError::Parse(format!("record {}: column {} is {}", record, i, value)))
+                }
+                None => Err(LoadError::Parse(format!("record {}: missing column {}", record, i))),
+            }
+        };
+
+        let mut coords = [0.0; 3];
+        for (axis, &i) in self.position.iter().enumerate() {
+            coords[axis] = get(i)?;
+        }
+        let mut attractor = template.clone();
+        attractor.position = P::from_coords(&coords[..self.position.len()]);
+        if let Some(i) = self.strength {
+            attractor.strength = get(i)?;
+        }
+        if let Some(i) = self.attract_dist {
+            attractor.attract_dist = SqDist::from_dist(get(i)?);
+        }
+        if let Some(i) = self.connect_dist {
+            attractor.connect_dist = SqDist::from_dist(get(i)?);
+        }
+        Ok(attractor)
+    }
+}
+
+fn parse_number(token: &str, line: usize) -> Result<f64, LoadError> {
+    token.trim()
+         .parse()
+         .map_err(|_| LoadError::Parse(format!("line {}: invalid number {:?}", line, token)))
+}
+
+/// Reads an XYZ file: one point per line, values separated by
+/// whitespace. Empty lines and lines starting with ```#``` are skipped.
+pub fn read_xyz<P, I, R>(reader: R,
+                         columns: &Columns,
+                         template: &Attractor<P, I>)
+                         -> Result<Vec<Attractor<P, I>>, LoadError>
+    where P: Coords,
+          I: Clone,
+          R: BufRead
+{
+    let resolved = Resolved::new(columns, P::dimensions(), None)?;
+    let mut attractors = Vec::new();
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let row = resolved.parse_row(line.split_whitespace(), n + 1)?;
+        attractors.push(resolved.attractor(&row, n + 1, template)?);
+    }
+    Ok(attractors)
+}
+
+pub fn open_xyz<P, I, Q>(path: Q,
+                         columns: &Columns,
+                         template: &Attractor<P, I>)
+                         -> Result<Vec<Attractor<P, I>>, LoadError>
+    where P: Coords,
+          I: Clone,
+          Q: AsRef<Path>
+{
+    read_xyz(BufReader::new(File::open(path)?), columns, template)
+}
+
+/// Reads a CSV file with values separated by ```delimiter```. If
+/// ```has_header``` is set, the first line names the columns. Fields may
+/// not be quoted.
+pub fn read_csv<P, I, R>(reader: R,
+                         delimiter: char,
+                         has_header: bool,
+                         columns: &Columns,
+                         template: &Attractor<P, I>)
+                         -> Result<Vec<Attractor<P, I>>, LoadError>
+    where P: Coords,
+          I: Clone,
+          R: BufRead
+{
+    let mut lines = reader.lines().enumerate();
+    let names = if has_header {
+        match lines.next() {
+            Some((_, line)) => {
+                Some(line?.split(delimiter)
+                          .map(|name| name.trim().to_string())
+                          .collect::<Vec<_>>())
+            }
+            None => return Ok(Vec::new()),
+        }
+    } else {
+        None
+    };
+    let resolved = Resolved::new(columns, P::dimensions(), names.as_ref().map(|n| &n[..]))?;
+
+    let mut attractors = Vec::new();
+    for (n, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = resolved.parse_row(line.split(delimiter), n + 1)?;
+        attractors.push(resolved.attractor(&row, n + 1, template)?);
+    }
+    Ok(attractors)
+}
+
+pub fn open_csv<P, I, Q>(path: Q,
+                         delimiter: char,
+                         has_header: bool,
+                         columns: &Columns,
+                         template: &Attractor<P, I>)
+                         -> Result<Vec<Attractor<P, I>>, LoadError>
+    where P: Coords,
+          I: Clone,
+          Q: AsRef<Path>
+{
+    read_csv(BufReader::new(File::open(path)?),
+             delimiter,
+             has_header,
+             columns,
+             template)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum PlyType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Result<PlyType, LoadError> {
+        Ok(match name {
+            "char" | "int8" => PlyType::I8,
+            "uchar" | "uint8" => PlyType::U8,
+            "short" | "int16" => PlyType::I16,
+            "ushort" | "uint16" => PlyType::U16,
+            "int" | "int32" => PlyType::I32,
+            "uint" | "uint32" => PlyType::U32,
+            "float" | "float32" => PlyType::F32,
+            "double" | "float64" => PlyType::F64,
+            _ => return Err(LoadError::Parse(format!("unknown PLY type {:?}", name))),
+        })
+    }
+
+    fn size(&self) -> usize {
+        match *self {
+            PlyType::I8 | PlyType::U8 => 1,
+            PlyType::I16 | PlyType::U16 => 2,
+            PlyType::I32 | PlyType::U32 | PlyType::F32 => 4,
+            PlyType::F64 => 8,
+        }
+    }
+
+    fn read<R: Read>(&self, reader: &mut R, format: PlyFormat) -> Result<f64, LoadError> {
+        let mut buf = [0u8; 8];
+        let size = self.size();
+        reader.read_exact(&mut buf[..size])?;
+        if format == PlyFormat::BinaryBigEndian {
+            buf[..size].reverse();
+        }
+        // ```buf``` is little endian now.
+        let mut bits = 0u64;
+        for i in (0..size).rev() {
+            bits = bits << 8 | buf[i] as u64;
+        }
+        Ok(match *self {
+            PlyType::I8 => bits as u8 as i8 as f64,
+            PlyType::U8 => bits as u8 as f64,
+            PlyType::I16 => bits as u16 as i16 as f64,
+            PlyType::U16 => bits as u16 as f64,
+            PlyType::I32 => bits as u32 as i32 as f64,
+            PlyType::U32 => bits as u32 as f64,
+            PlyType::F32 => f32::from_bits(bits as u32) as f64,
+            PlyType::F64 => f64::from_bits(bits),
+        })
+    }
+}
+
+#[derive(Debug)]
+enum PlyProperty {
+    Scalar(PlyType),
+    List {
+        count: PlyType,
+        item: PlyType,
+    },
+}
+
+#[derive(Debug)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<(String, PlyProperty)>,
+}
+
+/// Reads one line of the header, including the newline.
+fn read_header_line<R: BufRead>(reader: &mut R) -> Result<String, LoadError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(LoadError::Parse("unexpected end of PLY header".to_string()));
+    }
+    Ok(line.trim().to_string())
+}
+
+/// Reads a PLY file, ASCII or binary. The attractors are read from the
+/// ```vertex``` element, whose properties are the columns.
+pub fn read_ply<P, I, R>(mut reader: R,
+                         columns: &Columns,
+                         template: &Attractor<P, I>)
+                         -> Result<Vec<Attractor<P, I>>, LoadError>
+    where P: Coords,
+          I: Clone,
+          R: BufRead
+{
+    if read_header_line(&mut reader)? != "ply" {
+        return Err(LoadError::Parse("not a PLY file".to_string()));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    loop {
+        let line = read_header_line(&mut reader)?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().cloned() {
+            Some("format") if tokens.len() == 3 => {
+                format = Some(match tokens[1] {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    other => return Err(LoadError::Unsupported(format!("PLY format {}", other))),
+                });
+            }
+            Some("element") if tokens.len() == 3 => {
+                elements.push(PlyElement {
+                    name: tokens[1].to_string(),
+                    count: parse_number(tokens[2], 0)? as usize,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = match elements.last_mut() {
+                    Some(element) => element,
+                    None => return Err(LoadError::Parse("property before element".to_string())),
+                };
+                let property = match (tokens.get(1).cloned(), tokens.len()) {
+                    (Some("list"), 5) => {
+                        (tokens[4].to_string(),
+                         PlyProperty::List {
+                            count: PlyType::parse(tokens[2])?,
+                            item: PlyType::parse(tokens[3])?,
+                        })
+                    }
+                    (Some(ty), 3) => {
+                        (tokens[2].to_string(), PlyProperty::Scalar(PlyType::parse(ty)?))
+                    }
+                    _ => return Err(LoadError::Parse(format!("invalid property {:?}", line))),
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => break,
+            Some("comment") | Some("obj_info") | None => {}
+            _ => return Err(LoadError::Parse(format!("invalid header line {:?}", line))),
+        }
+    }
+    let format = match format {
+        Some(format) => format,
+        None => return Err(LoadError::Parse("missing PLY format".to_string())),
+    };
+
+    let mut attractors = Vec::new();
+    let mut ascii_lines = 0;
+    for element in elements.iter() {
+        let is_vertex = element.name == "vertex";
+        let resolved = if is_vertex {
+            let names: Vec<String> = element.properties.iter().map(|p| p.0.clone()).collect();
+            Some(Resolved::new(columns, P::dimensions(), Some(&names))?)
+        } else {
+            None
+        };
+
+        for _ in 0..element.count {
+            let mut row = Vec::with_capacity(element.properties.len());
+            if format == PlyFormat::Ascii {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                ascii_lines += 1;
+                let mut values = line.split_whitespace()
+                                     .map(|token| parse_number(token, ascii_lines));
+                let mut next = || -> Result<f64, LoadError> {
+                    match values.next() {
+                        Some(value) => value,
+                        None => {
+                            Err(LoadError::Parse(format!("element {} is too short",
+                                                         ascii_lines)))
+                        }
+                    }
+                };
+                for &(_, ref property) in element.properties.iter() {
+                    match *property {
+                        PlyProperty::Scalar(_) => row.push(next()?),
+                        PlyProperty::List { .. } => {
+                            let count = next()? as usize;
+                            for _ in 0..count {
+                                next()?;
+                            }
+                            row.push(count as f64);
+                        }
+                    }
+                }
+            } else {
+                for &(_, ref property) in element.properties.iter() {
+                    match *property {
+                        PlyProperty::Scalar(ty) => row.push(ty.read(&mut reader, format)?),
+                        PlyProperty::List { count, item } => {
+                            let count = count.read(&mut reader, format)? as usize;
+                            for _ in 0..count {
+                                item.read(&mut reader, format)?;
+                            }
+                            row.push(count as f64);
+                        }
+                    }
+                }
+            }
+
+            if let Some(ref resolved) = resolved {
+                attractors.push(resolved.attractor(&row, attractors.len(), template)?);
+            }
+        }
+
+        if is_vertex {
+            // The remaining elements are of no interest.
+            break;
+        }
+    }
+    Ok(attractors)
+}
+
+pub fn open_ply<P, I, Q>(path: Q,
+                         columns: &Columns,
+                         template: &Attractor<P, I>)
+                         -> Result<Vec<Attractor<P, I>>, LoadError>
+    where P: Coords,
+          I: Clone,
+          Q: AsRef<Path>
+{
+    read_ply(BufReader::new(File::open(path)?), columns, template)
+}
+
+#[cfg(test)]
+mod tests {
+    use na::{Pnt2, Pnt3};
+    use load::LoadError;
+    use {Attractor, ConnectAction, SqDist};
+    use super::*;
+
+    fn template<P>(position: P) -> Attractor<P, ()> {
+        Attractor {
+            attract_dist: SqDist::from_dist(1.0),
+            connect_dist: SqDist::from_dist(0.1),
+            strength: 1.0,
+            position: position,
+            information: (),
+            connect_action: ConnectAction::KillAttractor,
+            active_from_iteration: 0,
+            not_for_root: None,
+            not_for_connecting_root: None,
+        }
+    }
+
+    fn positions(attractors: &[Attractor<Pnt3<f32>, ()>]) -> Vec<Pnt3<f32>> {
+        attractors.iter().map(|ap| ap.position).collect()
+    }
+
+    const PLY_HEADER: &'static str = "element vertex 2\n\
+                                      property float x\n\
+                                      property float y\n\
+                                      property list uchar int neighbours\n\
+                                      property float z\n\
+                                      element face 1\n\
+                                      property list uchar int vertex_indices\n\
+                                      end_header\n";
+
+    #[test]
+    fn xyz_ignores_label_columns() {
+        let data = "# x y label strength\n1 2 tree 0.5\n\n3 4 bush 2\n";
+        let mut columns = Columns::xy();
+        columns.strength = Some(Column::Index(3));
+        let attractors = read_xyz(data.as_bytes(), &columns, &template(Pnt2::new(0.0, 0.0)))
+                             .unwrap();
+        assert_eq!(attractors.len(), 2);
+        assert_eq!(attractors[0].position, Pnt2::new(1.0, 2.0));
+        assert_eq!(attractors[0].strength, 0.5);
+        assert_eq!(attractors[1].position, Pnt2::new(3.0, 4.0));
+        assert_eq!(attractors[1].strength, 2.0);
+    }
+
+    #[test]
+    fn csv_looks_up_columns_by_header() {
+        let data = "label;z;y;x;attract_dist\na;3;2;1;0.5\nb;6;5;4;2\n";
+        let attractors = read_csv(data.as_bytes(),
+                                  ';',
+                                  true,
+                                  &Columns::named(),
+                                  &template(Pnt3::new(0.0, 0.0, 0.0)))
+                             .unwrap();
+        assert_eq!(positions(&attractors),
+                   vec![Pnt3::new(1.0, 2.0, 3.0), Pnt3::new(4.0, 5.0, 6.0)]);
+        assert_eq!(attractors[0].attract_dist, SqDist(0.25));
+        assert_eq!(attractors[1].attract_dist, SqDist(4.0));
+        // No strength column, so the template's is kept.
+        assert_eq!(attractors[0].strength, 1.0);
+    }
+
+    #[test]
+    fn csv_without_header_cannot_look_up_names() {
+        let result = read_csv("1,2,3\n".as_bytes(),
+                              ',',
+                              false,
+                              &Columns::named(),
+                              &template(Pnt3::new(0.0, 0.0, 0.0)));
+        match result {
+            Err(LoadError::Parse(_)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_finite_selected_values_are_rejected() {
+        for data in ["1 nan\n", "inf 2\n", "1e300 2\n"].iter() {
+            let result = read_xyz(data.as_bytes(), &Columns::xy(), &template(Pnt2::new(0.0, 0.0)));
+            match result {
+                Err(LoadError::Parse(_)) => {}
+                other => panic!("expected a parse error for {:?}, got {:?}", data, other),
+            }
+        }
+        // Unselected columns are not parsed.
+        let attractors = read_xyz("1 2 nan\n".as_bytes(),
+                                  &Columns::xy(),
+                                  &template(Pnt2::new(0.0, 0.0)))
+                             .unwrap();
+        assert_eq!(attractors[0].position, Pnt2::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn ascii_ply_skips_list_properties() {
+        let data = format!("ply\nformat ascii 1.0\ncomment two points\n{}\
+                            1 2 2 7 8 3\n\
+                            4 5 0 6\n\
+                            3 0 1 1\n",
+                           PLY_HEADER);
+        let attractors = read_ply(data.as_bytes(),
+                                  &Columns::named(),
+                                  &template(Pnt3::new(0.0, 0.0, 0.0)))
+                             .unwrap();
+        assert_eq!(positions(&attractors),
+                   vec![Pnt3::new(1.0, 2.0, 3.0), Pnt3::new(4.0, 5.0, 6.0)]);
+    }
+
+    #[test]
+    fn binary_little_endian_ply_skips_list_properties() {
+        let mut data = format!("ply\nformat binary_little_endian 1.0\n{}", PLY_HEADER).into_bytes();
+        for &(x, y, ref neighbours, z) in [(1.0f32, 2.0f32, vec![7i32, 8], 3.0f32),
+                                           (4.0, 5.0, vec![], 6.0)]
+                                              .iter() {
+            data.extend_from_slice(&x.to_le_bytes());
+            data.extend_from_slice(&y.to_le_bytes());
+            data.push(neighbours.len() as u8);
+            for n in neighbours.iter() {
+                data.extend_from_slice(&n.to_le_bytes());
+            }
+            data.extend_from_slice(&z.to_le_bytes());
+        }
+        data.push(3);
+        for i in 0..3i32 {
+            data.extend_from_slice(&i.to_le_bytes());
+        }
+
+        let attractors = read_ply(&data[..],
+                                  &Columns::named(),
+                                  &template(Pnt3::new(0.0, 0.0, 0.0)))
+                             .unwrap();
+        assert_eq!(positions(&attractors),
+                   vec![Pnt3::new(1.0, 2.0, 3.0), Pnt3::new(4.0, 5.0, 6.0)]);
+    }
+}
@@ -0,0 +1,553 @@
+//! Filling envelopes with attractors.
+//!
+//! All shapes are defined for 2D and 3D points. Shapes with an axis
+//! (cylinder, cone and the crown shape) are oriented along the y-axis.
+
+use na::{FloatPnt, FloatVec, Pnt2};
+use num::Zero;
+use rand::Rng;
+use std::fmt::Debug;
+use grid::Grid;
+use obstacle::polygon_contains;
+use {Attractor, Coords, InformationMerge, SpaceColonization};
+
+/// Rejection sampling gives up after this many misses in a row.
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// Number of candidates tried around each point by the Poisson-disk
+/// sampler before the point is retired.
+const POISSON_CANDIDATES: usize = 30;
+
+/// A region of space.
+pub trait Envelope<P> {
+    /// Returns true if ```position``` lies inside the envelope.
+    fn contains(&self, position: &P) -> bool;
+
+    /// Returns the minimum and maximum corner of an axis-aligned box
+    /// enclosing the envelope.
+    fn bounds(&self) -> (P, P);
+}
+
+/// A sphere (a disc in 2D).
+#[derive(Debug, Copy, Clone)]
+pub struct Sphere<P> {
+    pub center: P,
+    pub radius: f32,
+}
+
+impl<P: Coords> Envelope<P> for Sphere<P> {
+    fn contains(&self, position: &P) -> bool {
+        let sqdist = (0..P::dimensions()).fold(0.0, |sum, axis| {
+            let d = position.coord(axis) - self.center.coord(axis);
+            sum + d * d
+        });
+        sqdist <= self.radius * self.radius
+    }
+
+    fn bounds(&self) -> (P, P) {
+        let radii = [self.radius; 3];
+        (offset(&self.center, &radii, -1.0), offset(&self.center, &radii, 1.0))
+    }
+}
+
+/// An ellipsoid (an ellipse in 2D) with separate radii along each axis.
+#[derive(Debug, Copy, Clone)]
+pub struct Ellipsoid<P, F> {
+    pub center: P,
+    pub radii: F,
+}
+
+impl<P: Coords, F: Coords> Envelope<P> for Ellipsoid<P, F> {
+    fn contains(&self, position: &P) -> bool {
+        ellipsoid_sqdist(position, &self.center, &self.radii) <= 1.0
+    }
+
+    fn bounds(&self) -> (P, P) {
+        let radii = coords_of(&self.radii);
+        (offset(&self.center, &radii, -1.0), offset(&self.center, &radii, 1.0))
+    }
+}
+
+/// The upper half (y >= center.y) of an ellipsoid. A common tree crown.
+#[derive(Debug, Copy, Clone)]
+pub struct HalfEllipsoid<P, F> {
+    pub center: P,
+    pub radii: F,
+}
+
+impl<P: Coords, F: Coords> Envelope<P> for HalfEllipsoid<P, F> {
+    fn contains(&self, position: &P) -> bool {
+        position.coord(1) >= self.center.coord(1) &&
+        ellipsoid_sqdist(position, &self.center, &self.radii) <= 1.0
+    }
+
+    fn bounds(&self) -> (P, P) {
+        let radii = coords_of(&self.radii);
+        let (min, max) = (offset(&self.center, &radii, -1.0), offset(&self.center, &radii, 1.0));
+        let mut min = coords_of(&min);
+        min[1] = self.center.coord(1);
+        (P::from_coords(&min[..P::dimensions()]), max)
+    }
+}
+
+/// An axis-aligned box.
+#[derive(Debug, Copy, Clone)]
+pub struct Cuboid<P> {
+    pub min: P,
+    pub max: P,
+}
+
+impl<P: Coords> Envelope<P> for Cuboid<P> {
+    fn contains(&self, position: &P) -> bool {
+        (0..P::dimensions()).all(|axis| {
+            let x = position.coord(axis);
+            x >= self.min.coord(axis) && x <= self.max.coord(axis)
+        })
+    }
+
+    fn bounds(&self) -> (P, P) {
+        (self.min, self.max)
+    }
+}
+
+/// An upright cylinder standing on ```base``` (a rectangle in 2D).
+#[derive(Debug, Copy, Clone)]
+pub struct Cylinder<P> {
+    pub base: P,
+    pub radius: f32,
+    pub height: f32,
+}
+
+impl<P: Coords> Envelope<P> for Cylinder<P> {
+    fn contains(&self, position: &P) -> bool {
+        let h = position.coord(1) - self.base.coord(1);
+        h >= 0.0 && h <= self.height &&
+        radial_sqdist(position, &self.base) <= self.radius * self.radius
+    }
+
+    fn bounds(&self) -> (P, P) {
+        upright_bounds(&self.base, self.radius, self.height)
+    }
+}
+
+/// An upright cone standing on ```base```, with its apex ```height```
+/// above it (a triangle in 2D).
+#[derive(Debug, Copy, Clone)]
+pub struct Cone<P> {
+    pub base: P,
+    pub radius: f32,
+    pub height: f32,
+}
+
+impl<P: Coords> Envelope<P> for Cone<P> {
+    fn contains(&self, position: &P) -> bool {
+        let h = position.coord(1) - self.base.coord(1);
+        if h < 0.0 || h > self.height {
+            return false;
+        }
+        let r = self.radius * (1.0 - h / self.height);
+        radial_sqdist(position, &self.base) <= r * r
+    }
+
+    fn bounds(&self) -> (P, P) {
+        upright_bounds(&self.base, self.radius, self.height)
+    }
+}
+
+/// An arbitrary envelope given by a signed distance function, which is
+/// negative inside. It must lie within ```min``` and ```max```.
+pub struct Sdf<P, S> {
+    pub sdf: S,
+    pub min: P,
+    pub max: P,
+}
+
+impl<P, S> Sdf<P, S>
+    where S: Fn(&P) -> f32
+{
+    pub fn new(sdf: S, min: P, max: P) -> Sdf<P, S> {
+        Sdf {
+            sdf: sdf,
+            min: min,
+            max: max,
+        }
+    }
+}
+
+impl<P, S> Envelope<P> for Sdf<P, S>
+    where P: Coords,
+          S: Fn(&P) -> f32
+{
+    fn contains(&self, position: &P) -> bool {
+        (self.sdf)(position) <= 0.0
+    }
+
+    fn bounds(&self) -> (P, P) {
+        (self.min, self.max)
+    }
+}
+
+/// A 2D polygon, inside by the even-odd rule.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    pub vertices: Vec<Pnt2<f32>>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Pnt2<f32>>) -> Polygon {
+        Polygon { vertices: vertices }
+    }
+}
+
+impl Envelope<Pnt2<f32>> for Polygon {
+    fn contains(&self, position: &Pnt2<f32>) -> bool {
+        polygon_contains(&self.vertices, position)
+    }
+
+    fn bounds(&self) -> (Pnt2<f32>, Pnt2<f32>) {
+        let inf = ::std::f32::INFINITY;
+        self.vertices.iter().fold((Pnt2::new(inf, inf), Pnt2::new(-inf, -inf)),
+                                  |(min, max), v| {
+                                      (Pnt2::new(min.x.min(v.x), min.y.min(v.y)),
+                                       Pnt2::new(max.x.max(v.x), max.y.max(v.y)))
+                                  })
+    }
+}
+
+fn coords_of<P: Coords>(p: &P) -> [f32; 3] {
+    let mut coords = [0.0; 3];
+    for axis in 0..P::dimensions() {
+        coords[axis] = p.coord(axis);
+    }
+    coords
+}
+
+/// Returns ```p + sign * d``` for the per-axis offsets ```d```.
+fn offset<P: Coords>(p: &P, d: &[f32; 3], sign: f32) -> P {
+    let mut coords = coords_of(p);
+    for axis in 0..P::dimensions() {
+        coords[axis] += sign * d[axis];
+    }
+    P::from_coords(&coords[..P::dimensions()])
+}
+
+/// The square of the ellipsoidal distance, which is 1.0 on the surface.
+fn ellipsoid_sqdist<P: Coords, F: Coords>(position: &P, center: &P, radii: &F) -> f32 {
+    (0..P::dimensions()).fold(0.0, |sum, axis| {
+        let d = (position.coord(axis) - center.coord(axis)) / radii.coord(axis);
+        sum + d * d
+    })
+}
+
+/// The square distance from the vertical axis through ```base```.
+fn radial_sqdist<P: Coords>(position: &P, base: &P) -> f32 {
+    (0..P::dimensions()).filter(|&axis| axis != 1).fold(0.0, |sum, axis| {
+        let d = position.coord(axis) - base.coord(axis);
+        sum + d * d
+    })
+}
+
+fn upright_bounds<P: Coords>(base: &P, radius: f32, height: f32) -> (P, P) {
+    let mut min = coords_of(base);
+    let mut max = min;
+    for axis in 0..P::dimensions() {
+        if axis == 1 {
+            max[axis] += height;
+        } else {
+            min[axis] -= radius;
+            max[axis] += radius;
+        }
+    }
+    (P::from_coords(&min[..P::dimensions()]), P::from_coords(&max[..P::dimensions()]))
+}
+
+/// Returns a point uniformly distributed within the box from ```min```
+/// to ```max```.
+pub fn sample_box<P, R>(min: &P, max: &P, rng: &mut R) -> P
+    where P: Coords,
+          R: Rng
+{
+    let mut coords = [0.0; 3];
+    for axis in 0..P::dimensions() {
+        let (lo, hi) = (min.coord(axis), max.coord(axis));
+        coords[axis] = lo + (hi - lo) * rng.gen::<f32>();
+    }
+    P::from_coords(&coords[..P::dimensions()])
+}
+
+/// Returns a vector of unit length pointing in a uniformly distributed
+/// direction.
+pub fn sample_direction<F, R>(rng: &mut R) -> F
+    where F: Coords,
+          R: Rng
+{
+    loop {
+        let mut coords = [0.0; 3];
+        for axis in 0..F::dimensions() {
+            coords[axis] = 2.0 * rng.gen::<f32>() - 1.0;
+        }
+        let sqnorm = coords.iter().fold(0.0, |sum, c| sum + c * c);
+        // Rejecting points outside the unit ball keeps the directions uniform.
+        if sqnorm > 1.0e-6 && sqnorm <= 1.0 {
+            let norm = sqnorm.sqrt();
+            for c in coords.iter_mut() {
+                *c /= norm;
+            }
+            return F::from_coords(&coords[..F::dimensions()]);
+        }
+    }
+}
+
+/// Returns a point uniformly distributed within ```envelope```, or
+/// ```None``` if none was found after many attempts.
+pub fn sample<P, E, R>(envelope: &E, rng: &mut R) -> Option<P>
+    where P: Coords,
+          E: Envelope<P>,
+          R: Rng
+{
+    let (min, max) = envelope.bounds();
+    for _ in 0..MAX_ATTEMPTS {
+        let p = sample_box(&min, &max, rng);
+        if envelope.contains(&p) {
+            return Some(p);
+        }
+    }
+    None
+}
+
+/// Returns up to ```n``` points uniformly distributed within ```envelope```.
+/// Fewer are returned only if the envelope is (nearly) empty.
+pub fn sample_points<P, E, R>(envelope: &E, n: usize, rng: &mut R) -> Vec<P>
+    where P: Coords,
+          E: Envelope<P>,
+          R: Rng
+{
+    let mut points = Vec::with_capacity(n);
+    while points.len() < n {
+        match sample(envelope, rng) {
+            Some(p) => points.push(p),
+            None => break,
+        }
+    }
+    points
+}
+
+/// Adds ```n``` default attractors uniformly distributed within
+/// ```envelope```. Returns the number of attractors added.
+pub fn add_attractors<P, F, I, E, R>(sc: &mut SpaceColonization<P, F, I>,
+                                     envelope: &E,
+                                     n: usize,
+                                     rng: &mut R)
+                                     -> usize
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug,
+          E: Envelope<P>,
+          R: Rng
+{
+    let points = sample_points(envelope, n, rng);
+    let count = points.len();
+    for p in points {
+        sc.add_default_attractor(p);
+    }
+    count
+}
+
+/// Like ```add_attractors```, but the attractors are copies of
+/// ```template``` placed within ```envelope```.
+pub fn add_attractors_like<P, F, I, E, R>(sc: &mut SpaceColonization<P, F, I>,
+                                          template: &Attractor<P, I>,
+                                          envelope: &E,
+                                          n: usize,
+                                          rng: &mut R)
+                                          -> usize
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug,
+          E: Envelope<P>,
+          R: Rng
+{
+    let points = sample_points(envelope, n, rng);
+    let count = points.len();
+    for p in points {
+        let mut attractor = template.clone();
+        attractor.position = p;
+        sc.add_attractor(attractor);
+    }
+    count
+}
+
+fn sqdist<P: Coords>(a: &P, b: &P) -> f32 {
+    (0..P::dimensions()).fold(0.0, |sum, axis| {
+        let d = a.coord(axis) - b.coord(axis);
+        sum + d * d
+    })
+}
+
+/// Returns a point uniformly distributed in the spherical shell between
+/// ```min_dist``` and ```2 * min_dist``` around ```center```.
+fn sample_annulus<P, R>(center: &P, min_dist: f32, rng: &mut R) -> P
+    where P: Coords,
+          R: Rng
+{
+    let outer = [2.0 * min_dist; 3];
+    let (min, max) = (offset(center, &outer, -1.0), offset(center, &outer, 1.0));
+    loop {
+        let p = sample_box(&min, &max, rng);
+        let d = sqdist(&p, center);
+        if d >= min_dist * min_dist && d <= 4.0 * min_dist * min_dist {
+            return p;
+        }
+    }
+}
+
+/// Blue-noise points with a minimum spacing, placed by Bridson's
+/// Poisson-disk algorithm.
+struct PoissonDisk<P> {
+    min_dist: f32,
+    points: Vec<P>,
+    grid: Grid,
+}
+
+impl<P: Coords> PoissonDisk<P> {
+    fn new(min_dist: f32) -> PoissonDisk<P> {
+        PoissonDisk {
+            min_dist: min_dist,
+            points: Vec::new(),
+            // With this cell size, a cell never holds more than one
+            // point placed by the sampler.
+            grid: Grid::new(min_dist / (P::dimensions() as f32).sqrt()),
+        }
+    }
+
+    fn is_free(&self, p: &P) -> bool {
+        let mut free = true;
+        let points = &self.points;
+        let min_sqdist = self.min_dist * self.min_dist;
+        self.grid.visit_near(p, self.min_dist, &mut |i| {
+            if sqdist(&points[i], p) < min_sqdist {
+                free = false;
+            }
+        });
+        free
+    }
+
+    fn insert(&mut self, p: P) -> usize {
+        let idx = self.points.len();
+        self.grid.insert(&p, idx);
+        self.points.push(p);
+        idx
+    }
+}
+
+/// Returns points within ```envelope``` that are no closer than
+/// ```min_dist``` to each other, filling the envelope as densely as
+/// possible (Poisson-disk sampling). Returns no points unless
+/// ```min_dist``` is positive and finite.
+pub fn poisson_disk<P, E, R>(envelope: &E, min_dist: f32, rng: &mut R) -> Vec<P>
+    where P: Coords,
+          E: Envelope<P>,
+          R: Rng
+{
+    poisson_disk_around(envelope, min_dist, &[], rng)
+}
+
+/// Like ```poisson_disk```, but the new points also keep ```min_dist```
+/// away from the ```existing``` points. Only the new points are returned.
+/// The existing points themselves may be closer to each other. Returns
+/// no points unless ```min_dist``` is positive and finite.
+pub fn poisson_disk_around<P, E, R>(envelope: &E,
+                                    min_dist: f32,
+                                    existing: &[P],
+                                    rng: &mut R)
+                                    -> Vec<P>
+    where P: Coords,
+          E: Envelope<P>,
+          R: Rng
+{
+    // A zero distance would let every candidate pass and never finish.
+    if !(min_dist > 0.0 && min_dist.is_finite()) {
+        return Vec::new();
+    }
+    let (min, max) = envelope.bounds();
+    let bounds = Cuboid {
+        min: min,
+        max: max,
+    };
+    let mut disk = PoissonDisk::new(min_dist);
+    let mut active = Vec::new();
+    for &p in existing {
+        let idx = disk.insert(p);
+        if envelope.contains(&p) {
+            active.push(idx);
+        }
+    }
+    let first_new = disk.points.len();
+
+    loop {
+        while !active.is_empty() {
+            let a = rng.gen_range(0, active.len());
+            let center = disk.points[active[a]];
+            let mut found = false;
+            for _ in 0..POISSON_CANDIDATES {
+                let p = sample_annulus(&center, min_dist, rng);
+                if bounds.contains(&p) && envelope.contains(&p) && disk.is_free(&p) {
+                    let idx = disk.insert(p);
+                    active.push(idx);
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                active.swap_remove(a);
+            }
+        }
+
+        // Look for parts of the envelope not reached yet, e.g. when it is
+        // not connected.
+        let mut seed = None;
+        for _ in 0..POISSON_CANDIDATES {
+            match sample(envelope, rng) {
+                Some(p) => {
+                    if disk.is_free(&p) {
+                        seed = Some(p);
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        match seed {
+            Some(p) => {
+                let idx = disk.insert(p);
+                active.push(idx);
+            }
+            None => break,
+        }
+    }
+
+    disk.points.split_off(first_new)
+}
+
+/// Adds default attractors within ```envelope``` by Poisson-disk
+/// sampling, keeping ```min_dist``` away from each other and from the
+/// attractors already added. Returns the number of attractors added.
+pub fn add_poisson_attractors<P, F, I, E, R>(sc: &mut SpaceColonization<P, F, I>,
+                                             envelope: &E,
+                                             min_dist: f32,
+                                             rng: &mut R)
+                                             -> usize
+    where P: FloatPnt<f32, F> + Coords + Debug,
+          F: FloatVec<f32> + Coords + Zero + Copy + Debug,
+          I: InformationMerge + Default + Debug,
+          E: Envelope<P>,
+          R: Rng
+{
+    let existing: Vec<P> = sc.attractors().map(|a| a.position).collect();
+    let points = poisson_disk_around(envelope, min_dist, &existing, rng);
+    let count = points.len();
+    for p in points {
+        sc.add_default_attractor(p);
+    }
+    count
+}
@@ -0,0 +1,50 @@
+use na::{FloatPnt, FloatVec};
+
+fn clamp01(x: f32) -> f32 {
+    x.max(0.0).min(1.0)
+}
+
+/// The square distance between the segment from ```p1``` to ```q1``` and
+/// the segment from ```p2``` to ```q2```.
+pub fn segment_sqdist<P, F>(p1: &P, q1: &P, p2: &P, q2: &P) -> f32
+    where P: FloatPnt<f32, F>,
+          F: FloatVec<f32> + Copy
+{
+    // See Ericson, Real-Time Collision Detection, 5.1.9.
+    let epsilon = 1.0e-12;
+    let d1 = *q1 - *p1;
+    let d2 = *q2 - *p2;
+    let r = *p1 - *p2;
+    let a = d1.dot(&d1);
+    let e = d2.dot(&d2);
+    let f = d2.dot(&r);
+
+    let (s, t) = if a <= epsilon && e <= epsilon {
+        (0.0, 0.0)
+    } else if a <= epsilon {
+        (0.0, clamp01(f / e))
+    } else {
+        let c = d1.dot(&r);
+        if e <= epsilon {
+            (clamp01(-c / a), 0.0)
+        } else {
+            let b = d1.dot(&d2);
+            let denom = a * e - b * b;
+            let s = if denom != 0.0 {
+                clamp01((b * f - c * e) / denom)
+            } else {
+                0.0
+            };
+            let t = (b * s + f) / e;
+            if t < 0.0 {
+                (clamp01(-c / a), 0.0)
+            } else if t > 1.0 {
+                (clamp01((b - c) / a), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+
+    ((*p1 + d1 * s) - (*p2 + d2 * t)).sqnorm()
+}
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use {Node, NodeIdx};
+
+/// Iterates depth-first in pre-order over a subtree, following the
+/// direct parent links only.
+pub struct DepthFirst<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    nodes: &'a [Node<P, F, I>],
+    stack: Vec<NodeIdx>,
+}
+
+impl<'a, P, F, I> DepthFirst<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    pub fn new(nodes: &'a [Node<P, F, I>], start: NodeIdx) -> DepthFirst<'a, P, F, I> {
+        DepthFirst {
+            nodes: nodes,
+            stack: vec![start],
+        }
+    }
+}
+
+impl<'a, P, F, I> Iterator for DepthFirst<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    type Item = NodeIdx;
+
+    fn next(&mut self) -> Option<NodeIdx> {
+        let idx = match self.stack.pop() {
+            Some(idx) => idx,
+            None => return None,
+        };
+        // Pushed in reverse, so that the first child is visited first.
+        self.stack.extend(self.nodes[idx.0 as usize].children.iter().rev().cloned());
+        Some(idx)
+    }
+}
+
+/// Iterates breadth-first over a subtree, following the direct parent
+/// links only. Nodes closer to the start come first.
+pub struct BreadthFirst<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    nodes: &'a [Node<P, F, I>],
+    queue: VecDeque<NodeIdx>,
+}
+
+impl<'a, P, F, I> BreadthFirst<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    pub fn new(nodes: &'a [Node<P, F, I>], start: NodeIdx) -> BreadthFirst<'a, P, F, I> {
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        BreadthFirst {
+            nodes: nodes,
+            queue: queue,
+        }
+    }
+}
+
+impl<'a, P, F, I> Iterator for BreadthFirst<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    type Item = NodeIdx;
+
+    fn next(&mut self) -> Option<NodeIdx> {
+        let idx = match self.queue.pop_front() {
+            Some(idx) => idx,
+            None => return None,
+        };
+        self.queue.extend(self.nodes[idx.0 as usize].children.iter().cloned());
+        Some(idx)
+    }
+}
+
+/// Iterates from a node along the direct parent links up to its root.
+/// Yields the node first and the root last.
+pub struct PathToRoot<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    nodes: &'a [Node<P, F, I>],
+    next: Option<NodeIdx>,
+}
+
+impl<'a, P, F, I> PathToRoot<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    pub fn new(nodes: &'a [Node<P, F, I>], start: NodeIdx) -> PathToRoot<'a, P, F, I> {
+        PathToRoot {
+            nodes: nodes,
+            next: Some(start),
+        }
+    }
+}
+
+impl<'a, P, F, I> Iterator for PathToRoot<'a, P, F, I>
+    where P: Debug + 'a,
+          F: Debug + 'a,
+          I: Clone + Debug + 'a
+{
+    type Item = NodeIdx;
+
+    fn next(&mut self) -> Option<NodeIdx> {
+        let idx = match self.next {
+            Some(idx) => idx,
+            None => return None,
+        };
+        self.next = self.nodes[idx.0 as usize].parent();
+        Some(idx)
+    }
+}
+
+/// The deepest node that lies on the paths of both ```a``` and ```b```
+/// to their root, or ```None``` if they belong to different trees.
+pub fn lowest_common_ancestor<P, F, I>(nodes: &[Node<P, F, I>],
+                                       a: NodeIdx,
+                                       b: NodeIdx)
+                                       -> Option<NodeIdx>
+    where P: Debug,
+          F: Debug,
+          I: Clone + Debug
+{
+    if nodes[a.0 as usize].root != nodes[b.0 as usize].root {
+        return None;
+    }
+
+    // Lift the deeper node to the length of the other, then lift both
+    // until they meet.
+    let (mut a, mut b) = (a, b);
+    while nodes[a.0 as usize].length > nodes[b.0 as usize].length {
+        a = nodes[a.0 as usize].parent;
+    }
+    while nodes[b.0 as usize].length > nodes[a.0 as usize].length {
+        b = nodes[b.0 as usize].parent;
+    }
+    while a != b {
+        a = nodes[a.0 as usize].parent;
+        b = nodes[b.0 as usize].parent;
+    }
+    Some(a)
+}
@@ -0,0 +1,37 @@
+use na::FloatVec;
+use num::Zero;
+use try_normalize;
+
+/// A constant pull in one direction, like gravity (gravitropism) or
+/// light (phototropism), that is blended into the growth direction
+/// of every node.
+#[derive(Debug, Copy, Clone)]
+pub struct Tropism<F> {
+    /// The direction of the pull. Need not be normalized.
+    pub direction: F,
+
+    /// The strength of the pull relative to the combined pull of the
+    /// attractors of a node. With a weight of 1.0, both pull equally
+    /// strong. A negative weight pulls in the opposite direction.
+    pub weight: f32,
+}
+
+impl<F> Tropism<F>
+    where F: FloatVec<f32> + Zero + Copy
+{
+    pub fn new(direction: F, weight: f32) -> Tropism<F> {
+        Tropism {
+            direction: direction,
+            weight: weight,
+        }
+    }
+
+    /// Returns the pull on a node whose attractors pull with ```growth```.
+    /// A zero direction does not pull at all.
+    pub fn pull(&self, growth: F) -> F {
+        match try_normalize(self.direction) {
+            Some(direction) => direction * (self.weight * growth.norm()),
+            None => F::zero(),
+        }
+    }
+}